@@ -0,0 +1,109 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{is_known_compound, is_known_word};
+
+// tokens longer than this are never words, e.g. pasted data or keyboard mashing
+const BLOB_MAX_TOKEN_LENGTH: usize = 80;
+// shorter tokens are never blobs (hex/base64 patterns are too common in short words)
+const BLOB_MIN_LENGTH: usize = 16;
+// a unit repeated this many times is a pattern, e.g. xqzxqzxqzxqz
+const BLOB_MIN_REPEATS: usize = 4;
+const BLOB_MAX_REPEATED_UNIT_LENGTH: usize = 8;
+// base64 switches letter case/digits every 1-2 characters, CamelCase words or hashtags much less,
+// e.g. SGVsbG8gd29ybGQ= => 1.3, BlackLivesMatter2020 => 2.9
+const BASE64_BLOB_MAX_MEAN_RUN_LENGTH: f64 = 2.0;
+
+// tokens split at key/value separators too, e.g. key=SGVsbG8=, with base64 padding kept
+static BLOB_TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\s:=]+=*").unwrap());
+// hex data or hash, e.g. md5, sha1, sha256, 0xdeadbeef...
+static HEX_BLOB_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?:0x)?[0-9a-fA-F]+$").unwrap());
+static BASE64_BLOB_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9+/_-]+={0,2}$").unwrap());
+
+/// Get the short unit a token is made of, repeated, e.g. abcabcabcabc => abc
+fn get_repeated_unit(token: &str) -> Option<String> {
+    let letters = token.chars().collect::<Vec<char>>();
+    (1..=BLOB_MAX_REPEATED_UNIT_LENGTH)
+        .find(|unit_length| {
+            letters.len().is_multiple_of(*unit_length)
+                && letters.len() / unit_length >= BLOB_MIN_REPEATS
+                && letters
+                    .chunks(*unit_length)
+                    .all(|chunk| chunk == &letters[..*unit_length])
+        })
+        .map(|unit_length| letters[..unit_length].iter().collect())
+}
+
+/// Get the mean length of runs of the same character class (uppercase, lowercase, digit, other),
+/// e.g. aBc1 => 1.0, HelloWorld => 2.5
+fn get_mean_class_run_length(token: &str) -> f64 {
+    let class = |letter: char| {
+        if letter.is_ascii_uppercase() {
+            0
+        } else if letter.is_ascii_lowercase() {
+            1
+        } else if letter.is_ascii_digit() {
+            2
+        } else {
+            3
+        }
+    };
+    let classes = token.chars().map(class).collect::<Vec<u8>>();
+    let runs = 1 + classes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    classes.len() as f64 / runs as f64
+}
+
+/// Check if a repeated unit is a word (or two), e.g. fuckfuckfuckfuck, hahahahaha, fuckyoufuckyou...
+fn is_repeated_word(unit: &str) -> bool {
+    let unit = unit.to_lowercase();
+    unit.chars().all(|letter| letter.is_alphabetic())
+        && (is_known_word(&unit) || is_known_compound(&unit))
+}
+
+/// Check if a token is a non-linguistic blob: too long, hex, base64, hash or repeated pattern
+/// (not repeated words, they are emphasis, e.g. hahahahahahahaha).
+pub fn is_blob(token: &str) -> bool {
+    let length = token.chars().count();
+    if length > BLOB_MAX_TOKEN_LENGTH {
+        return true;
+    }
+    if length < BLOB_MIN_LENGTH {
+        return false;
+    }
+    let has_digit = token.chars().any(|letter| letter.is_ascii_digit());
+    let has_lowercase = token.chars().any(|letter| letter.is_ascii_lowercase());
+    let has_uppercase = token.chars().any(|letter| letter.is_ascii_uppercase());
+    if HEX_BLOB_REGEX.is_match(token) && has_digit && (has_lowercase || has_uppercase) {
+        return true;
+    }
+    if BASE64_BLOB_REGEX.is_match(token)
+        && has_digit
+        && has_lowercase
+        && has_uppercase
+        && get_mean_class_run_length(token) <= BASE64_BLOB_MAX_MEAN_RUN_LENGTH
+    {
+        return true;
+    }
+    get_repeated_unit(token).is_some_and(|unit| !is_repeated_word(&unit))
+}
+
+/// Replace blobs (hex, base64, hashes, repeated patterns, very long tokens) with placeholder.
+pub fn replace_blobs(text: &str, output: &mut String) {
+    let mut last_end = 0;
+    for m in BLOB_TOKEN_REGEX.find_iter(text) {
+        // surrounding punctuation is kept, e.g. (hash: 9e107d9d372bb6826bd81d3542a419d6)
+        let is_trimmed = |letter: char| !letter.is_alphanumeric() && !"+/=".contains(letter);
+        let token = m.as_str().trim_start_matches(is_trimmed);
+        let start = m.end() - token.len();
+        let token = token.trim_end_matches(is_trimmed);
+        // hashtags and mentions are handled by their own stages, e.g. #BlackLivesMatter2020
+        if text[..start].ends_with(['#', '@']) || !is_blob(token) {
+            continue;
+        }
+        output.push_str(&text[last_end..start]);
+        output.push_str(" (blob) ");
+        last_end = start + token.len();
+    }
+    output.push_str(&text[last_end..]);
+}
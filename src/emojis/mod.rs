@@ -31,11 +31,8 @@ pub static UNICODE_EMOJIS: Lazy<HashMap<String, String>> = Lazy::new(|| {
             {
                 return None;
             }
-            let e_skin_tones = e.skin_tones();
-            if e_skin_tones.is_none() {
-                return None;
-            }
-            Some(e_skin_tones.unwrap().collect::<Vec<&Emoji>>())
+            let e_skin_tones = e.skin_tones()?;
+            Some(e_skin_tones.collect::<Vec<&Emoji>>())
         })
         .flatten()
         .map(|e| {
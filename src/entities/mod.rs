@@ -0,0 +1,312 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::fmt::Write;
+
+use crate::is_standalone;
+
+// entities replaced by their placeholders, the others are kept as is (but still normalized)
+const ENTITY_PLACEHOLDER_KINDS: [&str; 5] = ["date", "time", "duration", "percentage", "money"];
+
+const MONTHS: &str = r"(jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)\.?";
+
+struct EntityPattern {
+    name: &'static str,
+    placeholder: &'static str,
+    regex: Regex,
+    // normalized value of a match, None if the match is invalid (e.g. 25:99)
+    normalize: fn(&Captures) -> Option<String>,
+}
+
+impl EntityPattern {
+    fn new(
+        name: &'static str,
+        placeholder: &'static str,
+        regex: &str,
+        normalize: fn(&Captures) -> Option<String>,
+    ) -> Self {
+        EntityPattern {
+            name,
+            placeholder,
+            regex: Regex::new(&format!("(?i){}", regex.replace("MONTHS", MONTHS))).unwrap(),
+            normalize,
+        }
+    }
+}
+
+/// Ordered from most to least specific, a match is replaced before the next pattern runs.
+static ENTITY_PATTERNS: Lazy<Vec<EntityPattern>> = Lazy::new(|| {
+    vec![
+        // wikipedia signature timestamp, e.g. 12:34, 5 June 2010 (UTC)
+        EntityPattern::new(
+            "date",
+            " (date) ",
+            r"(\d{1,2}):(\d{2}), (\d{1,2}) MONTHS,? (\d{4}) \(UTC\)",
+            |caps| {
+                let date = normalize_date(&caps[5], month_number(&caps[4])?, &caps[3])?;
+                let time = normalize_time(&caps[1], &caps[2], None, None)?;
+                Some(format!("{}T{}Z", date, time))
+            },
+        ),
+        EntityPattern::new("date", " (date) ", r"(\d{4})-(\d{1,2})-(\d{1,2})", |caps| {
+            normalize_date(&caps[1], caps[2].parse().ok()?, &caps[3])
+        }),
+        EntityPattern::new(
+            "date",
+            " (date) ",
+            r"(\d{1,2})(?:st|nd|rd|th)? (?:of )?MONTHS,? (\d{4})",
+            |caps| normalize_date(&caps[3], month_number(&caps[2])?, &caps[1]),
+        ),
+        EntityPattern::new(
+            "date",
+            " (date) ",
+            r"MONTHS (\d{1,2})(?:st|nd|rd|th)?,? (\d{4})",
+            |caps| normalize_date(&caps[3], month_number(&caps[1])?, &caps[2]),
+        ),
+        // month first (US) if possible, e.g. 06/05/2010 => 2010-06-05, 25/12/2010 => 2010-12-25
+        EntityPattern::new(
+            "date",
+            " (date) ",
+            r"(\d{1,2})([/.-])(\d{1,2})[/.-](\d{4}|\d{2})",
+            |caps| {
+                // dotted with 2-digit year is rather a version, e.g. 10.04.12
+                if &caps[2] == "." && caps[4].len() == 2 {
+                    return None;
+                }
+                let first: u32 = caps[1].parse().ok()?;
+                let (month, day) = match first {
+                    1..=12 => (first, &caps[3]),
+                    _ => (caps[3].parse().ok()?, &caps[1]),
+                };
+                normalize_date(&caps[4], month, day)
+            },
+        ),
+        EntityPattern::new("date", " (date) ", r"MONTHS (\d{4})", |caps| {
+            Some(format!("{}-{:02}", &caps[2], month_number(&caps[1])?))
+        }),
+        EntityPattern::new(
+            "time",
+            " (time) ",
+            r"(\d{1,2}):(\d{2})(?::(\d{2}))?(?:\s?([ap])\.?m\b\.?)?",
+            |caps| {
+                normalize_time(
+                    &caps[1],
+                    &caps[2],
+                    caps.get(3).map(|m| m.as_str()),
+                    caps.get(4).map(|m| m.as_str()),
+                )
+            },
+        ),
+        EntityPattern::new("time", " (time) ", r"(\d{1,2})\s?([ap])\.?m\b\.?", |caps| {
+            normalize_time(&caps[1], "00", None, Some(&caps[2]))
+        }),
+        EntityPattern::new(
+            "duration",
+            " (duration) ",
+            r"(\d+(?:\.\d+)?)\s?(seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?|months?|years?|yrs?)\b",
+            |caps| {
+                let unit = caps[2].to_lowercase();
+                let (prefix, designator) = match unit.as_str() {
+                    _ if unit.starts_with("s") => ("PT", "S"),
+                    _ if unit.starts_with("mi") => ("PT", "M"),
+                    _ if unit.starts_with("h") => ("PT", "H"),
+                    _ if unit.starts_with("d") => ("P", "D"),
+                    _ if unit.starts_with("w") => ("P", "W"),
+                    _ if unit.starts_with("mo") => ("P", "M"),
+                    _ => ("P", "Y"),
+                };
+                Some(format!("{}{}{}", prefix, &caps[1], designator))
+            },
+        ),
+        EntityPattern::new(
+            "percentage",
+            " (percentage) ",
+            r"(\d+(?:\.\d+)?)\s?(?:%|percent\b|per cent\b)",
+            |caps| Some((caps[1].parse::<f64>().ok()? / 100.0).to_string()),
+        ),
+        EntityPattern::new(
+            "money",
+            " (money) ",
+            r"([$€£¥₫])\s?(\d(?:[\d,]*\d)?(?:\.\d+)?)",
+            |caps| {
+                let currency = match &caps[1] {
+                    "$" => "USD",
+                    "€" => "EUR",
+                    "£" => "GBP",
+                    "¥" => "JPY",
+                    _ => "VND",
+                };
+                Some(format!("{} {:.2}", currency, normalize_number(&caps[2])?))
+            },
+        ),
+        EntityPattern::new(
+            "money",
+            " (money) ",
+            r"(\d(?:[\d,]*\d)?(?:\.\d+)?)\s?(usd|eur|gbp|jpy|vnd|dollars?|bucks|euros?|pounds?|dong)\b",
+            |caps| {
+                let currency = caps[2].to_lowercase();
+                let currency = match currency.as_str() {
+                    _ if currency.starts_with("dollar") || currency == "bucks" => "usd",
+                    _ if currency.starts_with("euro") => "eur",
+                    _ if currency.starts_with("pound") => "gbp",
+                    "dong" => "vnd",
+                    code => code,
+                };
+                let currency = currency.to_uppercase();
+                Some(format!("{} {:.2}", currency, normalize_number(&caps[1])?))
+            },
+        ),
+        EntityPattern::new("ordinal", " (ordinal) ", r"(\d+)(st|nd|rd|th)\b", |caps| {
+            let number: u64 = caps[1].parse().ok()?;
+            let suffix = match (number % 10, number % 100) {
+                (_, 11..=13) => "th",
+                (1, _) => "st",
+                (2, _) => "nd",
+                (3, _) => "rd",
+                _ => "th",
+            };
+            match caps[2].eq_ignore_ascii_case(suffix) {
+                true => Some(number.to_string()),
+                false => None,
+            }
+        }),
+        EntityPattern::new(
+            "number",
+            " (number) ",
+            r"\d(?:[\d,]*\d)?(?:\.\d+)?",
+            |caps| Some(normalize_number(&caps[0])?.to_string()),
+        ),
+    ]
+});
+
+/// Get month number from its (short) name, e.g. June => 6
+fn month_number(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    let month = match name.get(..3)? {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" => 8,
+        "sep" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+/// Get number of days in month, e.g. (2024, 2) => 29
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Normalize date to ISO 8601, e.g. (10, 6, 5) => 2010-06-05
+fn normalize_date(year: &str, month: u32, day: &str) -> Option<String> {
+    let mut year: u32 = year.parse().ok()?;
+    let day: u32 = day.parse().ok()?;
+    if year < 100 {
+        year += if year < 50 { 2000 } else { 1900 };
+    }
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Normalize time to 24-hour clock, e.g. (3, 15, None, p) => 15:15
+fn normalize_time(
+    hour: &str,
+    minute: &str,
+    second: Option<&str>,
+    meridiem: Option<&str>,
+) -> Option<String> {
+    let mut hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    if minute > 59 {
+        return None;
+    }
+    match meridiem.map(|m| m.to_lowercase()) {
+        Some(meridiem) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour = hour % 12 + if meridiem == "p" { 12 } else { 0 };
+        }
+        None if hour > 23 => return None,
+        None => {}
+    }
+    match second {
+        Some(second) => {
+            let second: u32 = second.parse().ok()?;
+            if second > 59 {
+                return None;
+            }
+            Some(format!("{:02}:{:02}:{:02}", hour, minute, second))
+        }
+        None => Some(format!("{:02}:{:02}", hour, minute)),
+    }
+}
+
+/// Parse number with thousands separators, e.g. 1,234.5 => 1234.5
+fn normalize_number(number: &str) -> Option<f64> {
+    number.replace(',', "").parse::<f64>().ok()
+}
+
+/// Find all entities (not overlapping, the first pattern wins) with their normalized values.
+fn find_entities(text: &str) -> Vec<(usize, usize, &'static EntityPattern, String)> {
+    let mut found: Vec<(usize, usize, &EntityPattern, String)> = Vec::new();
+    for pattern in ENTITY_PATTERNS.iter() {
+        for caps in pattern.regex.captures_iter(text) {
+            let m = caps.get(0).unwrap();
+            if found
+                .iter()
+                .any(|(start, end, _, _)| m.start() < *end && *start < m.end())
+            {
+                continue;
+            }
+            if !is_standalone(text, m.start(), m.end()) {
+                continue;
+            }
+            if let Some(value) = (pattern.normalize)(&caps) {
+                found.push((m.start(), m.end(), pattern, value));
+            }
+        }
+    }
+    found.sort_by_key(|(start, _, _, _)| *start);
+    found
+}
+
+/// Replace dates, times, durations, percentages, money... with their placeholders.
+pub fn replace_entities(text: &str, output: &mut String) {
+    let mut last_end = 0;
+    for (start, end, pattern, _) in find_entities(text) {
+        if !ENTITY_PLACEHOLDER_KINDS.contains(&pattern.name) {
+            continue;
+        }
+        output.push_str(&text[last_end..start]);
+        output.push_str(pattern.placeholder);
+        last_end = end;
+    }
+    output.push_str(&text[last_end..]);
+}
+
+/// Find all entities with normalized values, e.g. date=2010-06-05,time=15:15,money=USD 5.00
+pub fn extract_entities(text: &str, output: &mut String) {
+    let result = find_entities(text)
+        .iter()
+        .map(|(_, _, pattern, value)| format!("{}={}", pattern.name, value))
+        .collect::<Vec<String>>()
+        .join(",");
+    write!(output, "{}", result).unwrap();
+}
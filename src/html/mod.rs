@@ -0,0 +1,99 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::{collections::HashMap, fmt::Write};
+
+const BLOCK_TAGS: &str = "address|article|aside|blockquote|br|dd|div|dl|dt|figcaption|figure|footer|form|h[1-6]|header|hr|li|main|nav|ol|p|pre|section|table|tbody|td|tfoot|th|thead|tr|ul";
+const INLINE_TAGS: &str = "a|abbr|b|big|center|cite|code|del|dfn|em|font|i|img|input|ins|kbd|label|mark|q|s|samp|small|span|strike|strong|sub|sup|time|tt|u|var|wbr";
+
+static HTML_ENTITIES: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
+    HashMap::from([
+        ("amp", "&"),
+        ("lt", "<"),
+        ("gt", ">"),
+        ("quot", "\""),
+        ("apos", "'"),
+        ("nbsp", " "),
+        ("ndash", "–"),
+        ("mdash", "—"),
+        ("hellip", "…"),
+        ("lsquo", "‘"),
+        ("rsquo", "’"),
+        ("ldquo", "“"),
+        ("rdquo", "”"),
+        ("laquo", "«"),
+        ("raquo", "»"),
+        ("bull", "•"),
+        ("middot", "·"),
+        ("copy", "©"),
+        ("reg", "®"),
+        ("trade", "™"),
+        ("deg", "°"),
+        ("times", "×"),
+        ("divide", "÷"),
+        ("cent", "¢"),
+        ("pound", "£"),
+        ("yen", "¥"),
+        ("euro", "€"),
+    ])
+});
+
+static SCRIPT_STYLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)<script\b[^>]*>.*?(</script\s*>|$)|<style\b[^>]*>.*?(</style\s*>|$)|<!--.*?(-->|$)",
+    )
+    .unwrap()
+});
+static LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']?([^"'\s>]+)["']?[^>]*>(.*?)</a\s*>"#).unwrap()
+});
+static BLOCK_TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"(?i)</?(?:{})\b[^<>]*>", BLOCK_TAGS)).unwrap());
+static INLINE_TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"(?i)</?(?:{})\b[^<>]*>", INLINE_TAGS)).unwrap());
+static ENTITY_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&(?:#(\d{1,7})|#[xX]([0-9a-fA-F]{1,6})|([a-zA-Z]+));").unwrap());
+
+/// Decode named and numeric html entities, e.g. &amp; => &, &#39; => '
+fn decode_html_entities(text: &str) -> String {
+    ENTITY_REGEX
+        .replace_all(text, |caps: &Captures| {
+            let letter = if let Some(decimal) = caps.get(1) {
+                decimal
+                    .as_str()
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+            } else if let Some(hexadecimal) = caps.get(2) {
+                u32::from_str_radix(hexadecimal.as_str(), 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            } else {
+                let name = caps[3].to_lowercase();
+                return HTML_ENTITIES
+                    .get(name.as_str())
+                    .map_or(caps[0].to_owned(), |entity| entity.to_string());
+            };
+            letter.map_or(caps[0].to_owned(), |letter| letter.to_string())
+        })
+        .into_owned()
+}
+
+/// Strip html: drop script/style, keep link text (and its target), block elements to newlines, decode entities.
+pub fn clean_html(text: &str, output: &mut String) {
+    let mut result = SCRIPT_STYLE_REGEX.replace_all(text, " ").into_owned();
+    result = LINK_REGEX
+        .replace_all(&result, |caps: &Captures| {
+            let target = &caps[1];
+            let label = caps[2].trim();
+            if label.is_empty() || label == target {
+                format!(" {} ", target)
+            } else {
+                format!("{} {} ", label, target)
+            }
+        })
+        .into_owned();
+    result = BLOCK_TAG_REGEX.replace_all(&result, "\n").into_owned();
+    result = INLINE_TAG_REGEX.replace_all(&result, "").into_owned();
+    result = decode_html_entities(&result);
+    write!(output, "{}", result).unwrap();
+}
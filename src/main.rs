@@ -1,41 +1,277 @@
-use std::{env, time::Instant};
+use std::{env, process, time::Instant};
 
 use crate::emojis::*;
+use blobs::replace_blobs;
+use entities::{extract_entities, replace_entities};
+use html::clean_html;
+use markdown::{extract_markdown_marked, strip_markdown};
+use numerals::replace_numerals;
 use other_patterns::*;
+use pii::{extract_pii, replace_pii};
 use polars::{
     lazy::dsl::{col, lit, GetOutput},
     prelude::*,
 };
+use pseudonyms::*;
+use quotes::*;
+use resources::check_resources;
+use social::{extract_mentions, replace_mentions, split_hashtags};
 use spelling_corrector::*;
 use unicode::*;
-use urls::{replace_emails, replace_urls};
+use urls::{deobfuscate_links, extract_domain_categories, replace_emails, replace_urls};
 use utils::*;
+use vocabulary::learn_vocabulary;
+use wikitext::strip_wikitext;
 
+mod blobs;
 mod emojis;
+mod entities;
+mod html;
+mod markdown;
+mod numerals;
 mod other_patterns;
+mod pii;
+mod pseudonyms;
+mod quotes;
+mod resources;
+mod social;
 mod spelling_corrector;
 mod unicode;
 mod urls;
 mod utils;
+mod vocabulary;
+mod wikitext;
+
+// field of the corrections struct column, read from the corrections of a row
+type CorrectionField = (&'static str, fn(&[Correction]) -> String);
+
+const CORRECTION_FIELDS: [CorrectionField; 5] = [
+    ("m_ct", apply_corrections),
+    ("profane_corrections", extract_profane_corrections),
+    ("original_tokens", extract_original_tokens),
+    ("corrected_tokens", extract_corrected_tokens),
+    ("correction_trace", extract_correction_trace),
+];
+
+/// Correct tokens of each row once, into a struct column with a field per `CORRECTION_FIELDS`.
+fn correct_rows(s: Series) -> PolarsResult<Option<Series>> {
+    let ca = s.str()?;
+    let corrections = ca
+        .into_iter()
+        .map(|text| text.map(correct_text))
+        .collect::<Vec<Option<Vec<Correction>>>>();
+    let fields = CORRECTION_FIELDS
+        .iter()
+        .map(|(name, extract)| {
+            corrections
+                .iter()
+                .map(|corrections| corrections.as_deref().map(extract))
+                .collect::<StringChunked>()
+                .with_name(name)
+                .into_series()
+        })
+        .collect::<Vec<Series>>();
+    Ok(Some(StructChunked::new(ca.name(), &fields)?.into_series()))
+}
 
 fn main() {
+    // sample of the jigsaw dataset (shipped), another one is processed with `cargo run -- [dataset.csv]`
+    let default_dataset_filepath =
+        "data/trainning_dataset/jigsaw-toxic-comment-classification-challenge/sub_train.csv";
+    let command = env::args().nth(1);
+    // check data files, only print the report with `check-resources` command
+    let (report, is_complete, degraded) = check_resources();
+    if command.as_deref() == Some("check-resources") {
+        print!("{}", report);
+        process::exit(if is_complete { 0 } else { 1 });
+    }
+    if !degraded.is_empty() {
+        eprint!(
+            "Resources: degraded capabilities ({})\n{}",
+            degraded.join(", "),
+            report
+        );
+    }
+    if !is_complete {
+        eprintln!(
+            "Resources: required data files missing or invalid, run `check-resources` for details"
+        );
+        process::exit(1);
+    }
+    // learn supplemental dictionary with `learn-vocab [corpus.csv]` command
+    if command.as_deref() == Some("learn-vocab") {
+        let corpus_filepath = env::args().nth(2);
+        let corpus_filepath = corpus_filepath
+            .as_deref()
+            .unwrap_or(default_dataset_filepath);
+        learn_vocabulary(corpus_filepath, ENGLISH_LEARNED_VOCABULARY_FILEPATH);
+        return;
+    }
+    let dataset_filepath = command.as_deref().unwrap_or(default_dataset_filepath);
     let now = Instant::now();
     // main
     env::set_var("POLARS_FMT_STR_LEN", "120");
-    let dataset_filepath =
-        "data/trainning_dataset/jigsaw-toxic-comment-classification-challenge/train.csv";
 
-    let mut df = LazyCsvReader::new(dataset_filepath)
+    let mut lf = LazyCsvReader::new(dataset_filepath)
         .with_has_header(true)
         .finish()
         .unwrap()
         .with_column(col("comment_text").alias("m_ct"))
-        // replace ip addresses
-        .with_column(col("m_ct").str().replace_all(
-            lit(r"((25[0-5]|(2[0-4]|1\d|[1-9]|)\d)\.){3}(25[0-5]|(2[0-4]|1\d|[1-9]|)\d)"),
-            lit(r" (ip address) "),
-            false,
+        // mask protected terms, so that no stage alters them until they are restored
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_protected_terms);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("protected_terms"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(mask_protected_terms);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // remove wikipedia signatures, keep signers
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_wikipedia_signers);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("signer"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(remove_wikipedia_signatures);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
         ))
+        // strip wikitext markup
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(strip_wikitext);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // keep markdown spoilers/quotes, before quoted spans are replaced
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_markdown_marked);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("markdown_marked"),
+        )
+        // tag quoted spans, keep quotes
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_quotes);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("quoted"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_quotes);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // strip html tags, decode html entities
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(clean_html);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // strip markdown (spoilers/quotes are kept in markdown_marked column)
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(strip_markdown);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // replace user-defined patterns
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_pattern_rules);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // replace pii: ip/mac addresses, credit cards, ibans, national ids, phone numbers...
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_pii);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("pii"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_pii);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // deobfuscate emails/urls, e.g. john [at] gmail [dot] com
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(deobfuscate_links);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // flag urls/emails domains in blocklist
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_domain_categories);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("domain_category"),
+        )
         // replace emails
         .with_column(col("m_ct").map(
             |s| {
@@ -54,13 +290,87 @@ fn main() {
             },
             GetOutput::same_type(),
         ))
-        // replace time (not date)
-        .with_column(col("m_ct").str().replace_all(
-            lit(r"(([0-9]|0[0-9]|1[0-9]|2[0-3]):[0-5][0-9](:[0-5][0-9])?)"),
-            lit(r" (time) "),
-            false,
+        // replace mentions/subreddits, keep mentioned users
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_mentions);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("mentions"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_mentions);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // split hashtags into words
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(split_hashtags);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // replace non-linguistic blobs, after hashtags are split into words (hex, base64, hashes, repeated patterns)
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_blobs);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // replace entities: dates, times, durations, percentages, money
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_entities);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("entities"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_entities);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
+        // replace english slang/abbreviations, keep profane ones
+        .with_column(
+            col("m_ct")
+                .map(
+                    |s| {
+                        let ca = s.str()?;
+                        let out = ca.apply_to_buffer(extract_profane_slang);
+                        Ok(Some(out.into_series()))
+                    },
+                    GetOutput::same_type(),
+                )
+                .alias("profane_slang"),
+        )
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_english_slang);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
         ))
-        //? replace number with name?
         // replace english contractions
         .with_column(col("m_ct").map(
             |s| {
@@ -70,7 +380,15 @@ fn main() {
             },
             GetOutput::same_type(),
         ))
-        // replace wikipedia: shortcuts, (file) namespaces
+        // replace wikipedia: usernames (pseudonyms), shortcuts, (file) namespaces
+        .with_column(col("m_ct").map(
+            |s| {
+                let ca = s.str()?;
+                let out = ca.apply_to_buffer(replace_wikipedia_usernames);
+                Ok(Some(out.into_series()))
+            },
+            GetOutput::same_type(),
+        ))
         .with_column(col("m_ct").map(
             |s| {
                 let ca = s.str()?;
@@ -138,15 +456,85 @@ fn main() {
             lit(r" ${1} "),
             false,
         ))
-        // other process
+        // replace numerals with words
         .with_column(col("m_ct").map(
             |s| {
                 let ca = s.str()?;
-                let out = ca.apply_to_buffer(process_text);
+                let out = ca.apply_to_buffer(replace_numerals);
                 Ok(Some(out.into_series()))
             },
             GetOutput::same_type(),
         ))
+        // restore protected terms, the spelling corrector keeps them itself
+        .with_column(col("m_ct").map_many(
+            |s| {
+                let ca = s[0].str()?;
+                let terms = s[1].str()?;
+                let out = ca
+                    .into_iter()
+                    .zip(terms)
+                    .map(|(text, terms)| {
+                        let mut output = String::new();
+                        restore_protected_terms(text?, terms.unwrap_or_default(), &mut output);
+                        Some(output)
+                    })
+                    .collect::<StringChunked>()
+                    .with_name(ca.name());
+                Ok(Some(out.into_series()))
+            },
+            &[col("protected_terms")],
+            GetOutput::same_type(),
+        ));
+    // elongated words, a strong emphasis/toxicity signal
+    lf = lf.with_column(
+        col("m_ct")
+            .map(
+                |s| {
+                    let ca = s.str()?;
+                    let out = ca.apply_to_buffer(extract_elongated_words);
+                    Ok(Some(out.into_series()))
+                },
+                GetOutput::same_type(),
+            )
+            .alias("elongated"),
+    );
+    // correct tokens once per row, into a struct of the corrected text and correction columns
+    lf = lf.with_column(
+        col("m_ct")
+            .map(
+                correct_rows,
+                GetOutput::from_type(DataType::Struct(
+                    CORRECTION_FIELDS
+                        .iter()
+                        .map(|(name, _)| Field::new(name, DataType::String))
+                        .collect(),
+                )),
+            )
+            .alias("corrections"),
+    );
+    // words corrected into profanity, e.g. phuck => fuck
+    lf = lf.with_column(
+        col("corrections")
+            .struct_()
+            .field_by_name("profane_corrections")
+            .alias("profane_corrections"),
+    );
+    // original and corrected tokens (tab-separated, parallel) with correction trace, for training
+    if EMIT_CORRECTION_TOKENS {
+        lf = lf.with_columns(
+            ["original_tokens", "corrected_tokens", "correction_trace"]
+                .map(|name| col("corrections").struct_().field_by_name(name).alias(name)),
+        );
+    }
+    let mut df = lf
+        // other process
+        .with_column(
+            col("corrections")
+                .struct_()
+                .field_by_name("m_ct")
+                .alias("m_ct"),
+        )
+        .drop(["corrections", "protected_terms"])
         .collect()
         .unwrap();
     let mut file = std::fs::File::create("output.csv").unwrap();
@@ -0,0 +1,141 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::fmt::Write;
+
+// escaped ascii characters are moved to private use area while stripping
+const ESCAPE_OFFSET: u32 = 0xE000;
+
+static CODE_BLOCK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)```.*?(```|$)|~~~.*?(~~~|$)").unwrap());
+static INLINE_CODE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"`+[^`\n]+`+").unwrap());
+static SPOILER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r">!(.+?)!<").unwrap());
+static QUOTE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*(?:>[ \t]?)+(.*)$").unwrap());
+static IMAGE_OR_LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"!?\[([^\[\]]*)\]\(\s*<?([^\s()<>]+(?:\([^\s()]*\))?)>?(?:\s+["'(][^"')]*["')])?\s*\)"#,
+    )
+    .unwrap()
+});
+static AUTOLINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<((?:https?|ftp)://[^\s<>]+|[^\s<>@]+@[^\s<>@]+)>").unwrap());
+static EMPHASIS_REGEXES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r"\*\*(.+?)\*\*",
+        r"\b__(.+?)__\b",
+        r"~~(.+?)~~",
+        r"\*([^\s*](?:[^*\n]*[^\s*])?)\*",
+        r"\b_([^\s_](?:[^_\n]*[^\s_])?)_\b",
+    ]
+    .iter()
+    .map(|regex| Regex::new(regex).unwrap())
+    .collect()
+});
+static HEADING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*#{1,6}[ \t]+").unwrap());
+static LIST_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*(?:[-*+]|\d+[.)])[ \t]+").unwrap());
+static HORIZONTAL_RULE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*(?:[-*_][ \t]*){3,}$").unwrap());
+static ESCAPE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\\([\\`*_{}\[\]()#+\-.!>~|])").unwrap());
+
+/// Strip markdown: code spans/blocks to placeholder, keep link text (and its target), remove emphasis, headings, lists, quotes.
+pub fn strip_markdown(text: &str, output: &mut String) {
+    // hide escaped characters from markups, e.g. \* => U+E02A
+    let mut result = ESCAPE_REGEX
+        .replace_all(text, |caps: &Captures| {
+            let letter = caps[1].chars().next().unwrap();
+            char::from_u32(ESCAPE_OFFSET + letter as u32)
+                .unwrap()
+                .to_string()
+        })
+        .into_owned();
+    result = CODE_BLOCK_REGEX
+        .replace_all(&result, " (code) ")
+        .into_owned();
+    result = INLINE_CODE_REGEX
+        .replace_all(&result, " (code) ")
+        .into_owned();
+    result = SPOILER_REGEX.replace_all(&result, "${1}").into_owned();
+    result = IMAGE_OR_LINK_REGEX
+        .replace_all(&result, |caps: &Captures| {
+            let label = caps[1].trim();
+            match label.is_empty() {
+                true => format!(" {} ", &caps[2]),
+                false => format!("{} {} ", label, &caps[2]),
+            }
+        })
+        .into_owned();
+    result = AUTOLINK_REGEX.replace_all(&result, " ${1} ").into_owned();
+    for regex in EMPHASIS_REGEXES.iter() {
+        let source = result;
+        result = regex
+            .replace_all(&source, |caps: &Captures| {
+                let span = caps.get(0).unwrap();
+                let before = source[..span.start()].chars().next_back();
+                let after = source[span.end()..].chars().next();
+                // `*` inside words is not emphasis (masked profanity, arithmetic), e.g. f*ck, 2*3*4
+                let is_in_word = [before, after]
+                    .iter()
+                    .flatten()
+                    .any(|letter| letter.is_alphanumeric() || *letter == '*');
+                match span.as_str().starts_with('*') && is_in_word {
+                    true => span.as_str().to_owned(),
+                    false => caps[1].to_owned(),
+                }
+            })
+            .into_owned();
+    }
+    result = HORIZONTAL_RULE_REGEX.replace_all(&result, "").into_owned();
+    result = HEADING_REGEX.replace_all(&result, "").into_owned();
+    result = QUOTE_REGEX.replace_all(&result, "${1}").into_owned();
+    result = LIST_REGEX.replace_all(&result, "").into_owned();
+    result = result
+        .chars()
+        .map(|letter| match letter as u32 {
+            code if (ESCAPE_OFFSET..ESCAPE_OFFSET + 0x80).contains(&code) => {
+                char::from_u32(code - ESCAPE_OFFSET).unwrap()
+            }
+            _ => letter,
+        })
+        .collect();
+    write!(output, "{}", result).unwrap();
+}
+
+/// Find spoilers and quoted lines, e.g. spoiler: he dies | quote: you are wrong
+pub fn extract_markdown_marked(text: &str, output: &mut String) {
+    let text = CODE_BLOCK_REGEX.replace_all(text, " ");
+    let mut marked: Vec<String> = Vec::new();
+    for caps in SPOILER_REGEX.captures_iter(&text) {
+        marked.push(format!("spoiler: {}", caps[1].trim()));
+    }
+    let text = SPOILER_REGEX.replace_all(&text, " ");
+    for caps in QUOTE_REGEX.captures_iter(&text) {
+        let quote = caps[1].trim();
+        if !quote.is_empty() {
+            marked.push(format!("quote: {}", quote));
+        }
+    }
+    write!(output, "{}", marked.join(" | ")).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(text: &str) -> String {
+        let mut output = String::new();
+        strip_markdown(text, &mut output);
+        output
+    }
+
+    #[test]
+    fn strip_markdown_keeps_masked_words() {
+        assert_eq!(
+            strip("f*ck this sh*t and 2*3*4"),
+            "f*ck this sh*t and 2*3*4"
+        );
+        assert_eq!(strip("f**k you, s**t"), "f**k you, s**t");
+        assert_eq!(strip("*really* **bad** (*so*)"), "really bad (so)");
+    }
+}
@@ -0,0 +1,202 @@
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, fmt::Write, fs::File, io::BufReader};
+
+// optional stage, numerals are passed through if disabled
+const NUMERAL_NORMALIZATION: bool = true;
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+// u64::MAX is about 18 quintillion, so every scale multiplier stays below one thousand
+const SCALES: [(u64, &str); 6] = [
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+static NUMERAL_WORDS: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let numeral_words_filepath = "data/others/numeral_words.json";
+    let file = File::open(numeral_words_filepath).unwrap();
+    let reader = BufReader::new(file);
+    let numeral_words: HashMap<String, String> = serde_json::from_reader(reader).unwrap();
+    numeral_words
+        .into_iter()
+        .map(|(numeral_word, word)| (numeral_word.to_lowercase(), word))
+        .collect()
+});
+
+/// Spell out number below one thousand, e.g. 342 => three hundred forty two
+fn spell_hundreds(number: u64, words: &mut Vec<&'static str>) {
+    let hundreds = number / 100;
+    let rest = number % 100;
+    if hundreds > 0 {
+        words.push(ONES[hundreds as usize]);
+        words.push("hundred");
+    }
+    if rest >= 20 {
+        words.push(TENS[(rest / 10) as usize]);
+        if !rest.is_multiple_of(10) {
+            words.push(ONES[(rest % 10) as usize]);
+        }
+    } else if rest > 0 || hundreds == 0 {
+        words.push(ONES[rest as usize]);
+    }
+}
+
+/// Spell out cardinal number, e.g. 1205 => one thousand two hundred five
+pub fn spell_cardinal(number: u64) -> String {
+    let mut words = Vec::new();
+    let mut rest = number;
+    for (scale, scale_name) in SCALES {
+        if rest >= scale {
+            spell_hundreds(rest / scale, &mut words);
+            words.push(scale_name);
+            rest %= scale;
+        }
+    }
+    if rest > 0 || words.is_empty() {
+        spell_hundreds(rest, &mut words);
+    }
+    words.join(" ")
+}
+
+/// Spell out ordinal number, e.g. 21 => twenty first
+pub fn spell_ordinal(number: u64) -> String {
+    let cardinal = spell_cardinal(number);
+    let (head, last) = match cardinal.rsplit_once(' ') {
+        Some((head, last)) => (format!("{} ", head), last),
+        None => (String::new(), cardinal.as_str()),
+    };
+    let last = match last {
+        "one" => "first".to_owned(),
+        "two" => "second".to_owned(),
+        "three" => "third".to_owned(),
+        "five" => "fifth".to_owned(),
+        "eight" => "eighth".to_owned(),
+        "nine" => "ninth".to_owned(),
+        "twelve" => "twelfth".to_owned(),
+        _ if last.ends_with('y') => format!("{}ieth", last.trim_end_matches('y')),
+        _ => format!("{}th", last),
+    };
+    format!("{}{}", head, last)
+}
+
+/// Get the English suffix of an ordinal number, e.g. 1 => st, 12 => th, 22 => nd
+fn get_ordinal_suffix(number: u64) -> &'static str {
+    match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Spell out numeral, e.g. 2 => two, 1,000 => one thousand, 3.14 => three point one four, 1st => first
+fn spell_numeral(word: &str) -> Option<String> {
+    let lowercase_word = word.to_lowercase();
+    if let Some(spelled_word) = NUMERAL_WORDS.get(&lowercase_word) {
+        return Some(spelled_word.to_owned());
+    }
+    for suffix in ["st", "nd", "rd", "th"] {
+        if let Some(number) = lowercase_word.strip_suffix(suffix) {
+            let number = number.parse::<u64>().ok()?;
+            // mismatched suffixes are kept as is, e.g. 2st, 11st
+            if get_ordinal_suffix(number) != suffix {
+                return None;
+            }
+            return Some(spell_ordinal(number));
+        }
+    }
+    let (integer, fraction) = match word.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (word, None),
+    };
+    let integer = integer.replace(',', "");
+    if integer.is_empty() || !integer.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut result = spell_cardinal(integer.parse::<u64>().ok()?);
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        result.push_str(" point");
+        for digit in fraction.chars() {
+            write!(result, " {}", ONES[digit.to_digit(10)? as usize]).unwrap();
+        }
+    }
+    Some(result)
+}
+
+/// Convert numerals to words, e.g. 2 => two, 1st => first, 2nite => tonight, gr8 => great
+pub fn replace_numerals(text: &str, output: &mut String) {
+    if !NUMERAL_NORMALIZATION {
+        write!(output, "{}", text).unwrap();
+        return;
+    }
+    let result = text
+        .split_whitespace()
+        .map(|word| {
+            if !word.chars().any(|c| c.is_ascii_digit()) {
+                return word.to_owned();
+            }
+            spell_numeral(word).unwrap_or_else(|| word.to_owned())
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    write!(output, "{}", result).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spell_cardinal_large_numbers() {
+        assert_eq!(spell_cardinal(1_000_000_000_000_000_000), "one quintillion");
+        assert_eq!(
+            spell_cardinal(u64::MAX),
+            "eighteen quintillion four hundred forty six quadrillion seven hundred forty four \
+             trillion seventy three billion seven hundred nine million five hundred fifty one \
+             thousand six hundred fifteen"
+        );
+    }
+
+    #[test]
+    fn spell_numeral_validates_ordinal_suffixes() {
+        assert_eq!(spell_numeral("21st").as_deref(), Some("twenty first"));
+        assert_eq!(spell_numeral("12th").as_deref(), Some("twelfth"));
+        assert_eq!(
+            spell_numeral("113th").as_deref(),
+            Some("one hundred thirteenth")
+        );
+        assert_eq!(spell_numeral("2st"), None);
+        assert_eq!(spell_numeral("11st"), None);
+    }
+}
@@ -1,22 +1,66 @@
 use once_cell::sync::Lazy;
-use std::{collections::HashMap, fmt::Write, fs::File, io::BufReader};
+use regex::{Captures, Regex, RegexSet};
+use serde::Deserialize;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::Write,
+    fs::File,
+    io::{BufRead, BufReader},
+};
 
-use crate::aho_corasick_replace_all;
+use crate::{aho_corasick_replace_all, pseudonymize};
 
 pub const WIKIPEDIA_NAMESPACE_REGEX: &str = r#"(talk|user|wikipedia|wp|project|wt|template|tm|help|category|portal|draft|timedtext|module|special|topic|education program|book|gadget|gadget definition)((_| )talk)?:[\w\/#]+"#;
 pub const WIKIPEDIA_FILE_NAMESPACE_REGEX: &str =
     r#"(file|image)((_| )talk)?:([\w\s\(\)\&\-\"\']+)((\.(\w{3}))|,|\.|\)|\")"#;
 
+static WIKIPEDIA_USERNAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\buser(?:[ _]talk)?:([^\s|\[\]/#{}<>]+)").unwrap());
+
+// timestamp of wikipedia signature, day/month/year in any order, e.g. 12:34, 5 June 2010 (UTC),
+// 21:51, January 11, 2016 (UTC), 12:17 26 Jun, 2007 (UTC), 06:46, 2005 Feb 7 (UTC)
+const WIKIPEDIA_TIMESTAMP_REGEX: &str = r"\d{1,2}:\d{2}(?::\d{2})?,? (?:\d{1,2} [A-Za-z]{3,9}\.?,? \d{4}|[A-Za-z]{3,9}\.? \d{1,2},? \d{4}|\d{4} [A-Za-z]{3,9}\.? \d{1,2}) \(UTC\)";
+// talk/contribs links after the user link, e.g. ([[User talk:Foo|talk]] • [[Special:Contributions/Foo|contribs]])
+const WIKIPEDIA_TALK_REGEX: &str = r"\(\s*(?:\[\[(?:user[ _]talk:|special:contributions/)[^\]]*\]\]|talk|contribs)(?:\s*[•·|/,]\s*(?:\[\[(?:user[ _]talk:|special:contributions/)[^\]]*\]\]|talk|contribs))*\s*\)";
+
+// signatures with the signer (username or ip) in the first non-empty group, from most to least specific,
+// the signer is only taken from a user link or an unsigned comment note, never from plain text
+static WIKIPEDIA_SIGNATURE_REGEXES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // —Preceding unsigned comment added by [[Special:Contributions/1.2.3.4|1.2.3.4]] ([[User talk:1.2.3.4|talk]]) 12:34, 5 June 2010 (UTC)
+        r"(?i)[-—–~\s]*(?:(?:the\s+)?preceding\s+)?unsigned\s+comment\s+(?:was\s+)?added\s+by\s+(?:\[\[(?:special:contributions/|user:)([^|\]]+)(?:\|[^\]]*)?\]\]|([^\s()\[\]]+))\s*(?:TALK)?\s*(?:TIMESTAMP)?",
+        // —[[User:Foo|Foo]] ([[User talk:Foo|talk]]) 12:34, 5 June 2010 (UTC)
+        r"(?i)(?:[-—–~]{1,2}\s*)?\[\[user:([^|\]]+)(?:\|[^\]]*)?\]\]\s*(?:TALK\s*(?:TIMESTAMP)?|TIMESTAMP)",
+        // unexpanded signature, e.g. ~~~~
+        r"~{3,5}",
+        // signature with stripped username, e.g. (talk) 12:34, 5 June 2010 (UTC), |Talk 12:17 26 Jun, 2007 (UTC)
+        r"(?i)(?:[-—–~]{1,2}\s*)?(?:\|\s*talk\b|\(\s*talk(?:\s*[•·|/]\s*contribs)?\s*\)|\btalk\b)?\s*TIMESTAMP",
+    ]
+    .iter()
+    .map(|regex| {
+        let regex = regex
+            .replace("TIMESTAMP", WIKIPEDIA_TIMESTAMP_REGEX)
+            .replace("TALK", WIKIPEDIA_TALK_REGEX);
+        Regex::new(&regex).unwrap()
+    })
+    .collect()
+});
+
+static IP_ADDRESS_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d{1,3}\.){3}\d{1,3}$|^[0-9A-Fa-f:]+:[0-9A-Fa-f:]*$").unwrap());
+
+// whole words and suffixes, 's and 'd are disambiguated by the next word
 static ENGLISH_CONTRACTIONS: Lazy<HashMap<String, String>> = Lazy::new(|| {
     let result = HashMap::from([
         ("won't", "will not"),
         ("can't", "can not"),
+        ("shan't", "shall not"),
+        ("ain't", "is not"),
+        ("let's", "let us"),
         ("n't", " not"),
         ("'re", " are"),
-        ("'s", " is"),
-        ("'d", " would"),
         ("'ll", " will"),
-        ("'t", " not"),
         ("'ve", " have"),
         ("'m", " am"),
     ]);
@@ -26,18 +70,173 @@ static ENGLISH_CONTRACTIONS: Lazy<HashMap<String, String>> = Lazy::new(|| {
         .collect()
 });
 
+// 's is never possessive after these words
+const ENGLISH_IS_HAS_SUBJECTS: [&str; 24] = [
+    "it",
+    "that",
+    "there",
+    "here",
+    "what",
+    "who",
+    "where",
+    "when",
+    "why",
+    "how",
+    "he",
+    "she",
+    "this",
+    "everyone",
+    "everybody",
+    "everything",
+    "someone",
+    "somebody",
+    "something",
+    "nobody",
+    "nothing",
+    "anyone",
+    "anybody",
+    "anything",
+];
+// next words after 's meaning "is"
+const ENGLISH_IS_CUES: [&str; 40] = [
+    "a", "an", "the", "not", "no", "so", "very", "too", "really", "just", "always", "never",
+    "still", "also", "only", "quite", "pretty", "such", "all", "gonna", "here", "there", "in",
+    "on", "at", "out", "up", "down", "back", "over", "like", "about", "right", "wrong", "true",
+    "good", "bad", "stupid", "dumb", "being",
+];
+// next words after 's/'d meaning "has"/"had"
+const ENGLISH_HAS_HAD_CUES: [&str; 24] = [
+    "been", "got", "gotten", "had", "done", "gone", "seen", "made", "taken", "given", "become",
+    "said", "told", "left", "lost", "won", "shown", "known", "written", "already", "better",
+    "best", "come", "ever",
+];
+// apostrophes, also curly/modifier ones before unicode_decode
+const APOSTROPHES: [char; 5] = ['\'', '’', '‘', 'ʼ', '`'];
+
+static CONTRACTION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b[A-Za-z]+['’‘ʼ`][A-Za-z]+\b").unwrap());
+static NEXT_WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\s,]*([A-Za-z]+)").unwrap());
+
+#[allow(dead_code)]
+#[derive(PartialEq)]
+pub enum SlangMode {
+    /// Replace slang with its expansion, e.g. stfu => shut the fuck up
+    Expand,
+    /// Keep slang, add a placeholder after it, e.g. stfu => stfu (profane slang)
+    Tag,
+    /// Keep slang as is.
+    Keep,
+}
+
+pub const SLANG_MODE: SlangMode = SlangMode::Expand;
+
+struct Slang {
+    expansion: String,
+    is_profanity: bool,
+}
+
+static ENGLISH_SLANG: Lazy<HashMap<String, Slang>> = Lazy::new(|| {
+    let english_slang_filepath = "data/dictionaries/english/slang_abbreviations.txt";
+    let file = File::open(english_slang_filepath).unwrap();
+    let reader = BufReader::new(file);
+    let mut slang = HashMap::new();
+    for line in reader.lines() {
+        let line = line.unwrap();
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split('\t').collect::<Vec<&str>>();
+        slang.insert(
+            fields[0].trim().to_lowercase(),
+            Slang {
+                expansion: fields[1].trim().to_owned(),
+                is_profanity: fields
+                    .get(2)
+                    .is_some_and(|marker| marker.trim() == "profanity"),
+            },
+        );
+    }
+    slang
+});
+
+static SLANG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\w'’]+").unwrap());
+
+#[derive(Deserialize)]
+struct PatternRuleExample {
+    input: String,
+    output: String,
+}
+
+/// User-defined replacement rule, rules with higher priority are applied first.
+#[derive(Deserialize)]
+struct PatternRule {
+    name: String,
+    regex: String,
+    replacement: String,
+    #[serde(default)]
+    flags: String,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    examples: Vec<PatternRuleExample>,
+}
+
+impl PatternRule {
+    /// Get regex with inline flags, e.g. (?i)\bfoo
+    fn get_regex(&self) -> String {
+        match self.flags.is_empty() {
+            true => self.regex.clone(),
+            false => format!("(?{}){}", self.flags, self.regex),
+        }
+    }
+}
+
+struct PatternRules {
+    rules: Vec<(PatternRule, Regex)>,
+    set: RegexSet,
+}
+
+static PATTERN_RULES: Lazy<PatternRules> = Lazy::new(|| {
+    eprintln!("Other Patterns: pattern rules loading...");
+    let pattern_rules_filepath = "data/others/pattern_rules.json";
+    let file = File::open(pattern_rules_filepath).unwrap();
+    let reader = BufReader::new(file);
+    let mut rules: Vec<PatternRule> = serde_json::from_reader(reader).unwrap();
+    rules.sort_by_key(|rule| Reverse(rule.priority));
+    let rules = rules
+        .into_iter()
+        .map(|rule| {
+            let regex = Regex::new(&rule.get_regex())
+                .unwrap_or_else(|error| panic!("Pattern rule {}: {}", rule.name, error));
+            // validate examples
+            for example in rule.examples.iter() {
+                let output = regex.replace_all(&example.input, rule.replacement.as_str());
+                if output != example.output {
+                    panic!(
+                        "Pattern rule {}: {:?} => {:?}, expected {:?}",
+                        rule.name, example.input, output, example.output
+                    );
+                }
+            }
+            (rule, regex)
+        })
+        .collect::<Vec<(PatternRule, Regex)>>();
+    let set = RegexSet::new(rules.iter().map(|(rule, _)| rule.get_regex())).unwrap();
+    PatternRules { rules, set }
+});
+
 static WIKIPEDIA_SHORTCUTS: Lazy<HashMap<String, String>> = Lazy::new(|| {
     let wikipedia_shortcuts_filepath = "data/others/wiki_shortcuts.json";
     let file = File::open(wikipedia_shortcuts_filepath).unwrap();
     let reader = BufReader::new(file);
     let wikipedia_shortcuts: HashMap<String, String> = serde_json::from_reader(reader).unwrap();
     let keys = wikipedia_shortcuts
-        .iter()
-        .map(|(key, _)| (key.clone(), " (wikipedia shortcut) ".to_owned()))
+        .keys()
+        .map(|key| (key.clone(), " (wikipedia shortcut) ".to_owned()))
         .collect::<HashMap<String, String>>();
     let values = wikipedia_shortcuts
-        .iter()
-        .map(|(_, value)| (value.clone(), " (wikipedia shortcut) ".to_owned()))
+        .values()
+        .map(|value| (value.clone(), " (wikipedia shortcut) ".to_owned()))
         .collect::<HashMap<String, String>>();
     let mut result = HashMap::new();
     result.extend(keys.to_owned());
@@ -45,8 +244,77 @@ static WIKIPEDIA_SHORTCUTS: Lazy<HashMap<String, String>> = Lazy::new(|| {
     result
 });
 
+/// Expand 's to " is"/" has" or keep it as possessive, depend on the subject and the next word.
+fn expand_s_contraction(subject: &str, next_word: &str) -> Option<&'static str> {
+    if ENGLISH_HAS_HAD_CUES.contains(&next_word) {
+        return Some(" has");
+    }
+    if ENGLISH_IS_HAS_SUBJECTS.contains(&subject)
+        || ENGLISH_IS_CUES.contains(&next_word)
+        || (next_word.len() > 4 && next_word.ends_with("ing"))
+    {
+        return Some(" is");
+    }
+    None
+}
+
+/// Expand 'd to " had"/" would", depend on the next word.
+fn expand_d_contraction(next_word: &str) -> &'static str {
+    if ENGLISH_HAS_HAD_CUES.contains(&next_word)
+        || (next_word.len() > 4 && next_word.ends_with("ed"))
+    {
+        return " had";
+    }
+    " would"
+}
+
+/// Expand contraction word, e.g. don't => do not, he's gone => he has gone, John's car => John's car
+fn expand_contraction(word: &str, next_word: &str) -> Option<String> {
+    let normalized_word = word.replace(APOSTROPHES, "'").to_lowercase();
+    let (subject, suffix) = normalized_word.split_at(normalized_word.find('\'')?);
+    let subject_len = subject.len();
+    if let Some(expansion) = ENGLISH_CONTRACTIONS.get(&normalized_word) {
+        return Some(match_case(word, expansion));
+    }
+    // do|n't
+    if subject.ends_with('n') && suffix == "'t" {
+        let expansion = match_case(word, &ENGLISH_CONTRACTIONS["n't"]);
+        return Some(format!("{}{}", &word[..subject_len - 1], expansion));
+    }
+    let expansion = match suffix {
+        "'s" => expand_s_contraction(subject, next_word)?,
+        "'d" => expand_d_contraction(next_word),
+        _ => ENGLISH_CONTRACTIONS.get(suffix)?,
+    };
+    Some(format!(
+        "{}{}",
+        &word[..subject_len],
+        match_case(word, expansion)
+    ))
+}
+
+/// Uppercase expansion if the word is uppercase, e.g. DON'T => DO NOT
+fn match_case(word: &str, expansion: &str) -> String {
+    let letters = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<char>>();
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        return expansion.to_uppercase();
+    }
+    expansion.to_owned()
+}
+
+/// Expand english contractions at token boundaries, keep possessive 's.
 pub fn replace_english_contractions(text: &str, output: &mut String) {
-    let result = aho_corasick_replace_all(text, &ENGLISH_CONTRACTIONS);
+    let result = CONTRACTION_REGEX.replace_all(text, |caps: &Captures| {
+        let m = caps.get(0).unwrap();
+        let next_word = NEXT_WORD_REGEX
+            .captures(&text[m.end()..])
+            .map(|caps| caps[1].to_lowercase())
+            .unwrap_or_default();
+        expand_contraction(m.as_str(), &next_word).unwrap_or(m.as_str().to_owned())
+    });
     write!(output, "{}", result).unwrap();
 }
 
@@ -54,3 +322,127 @@ pub fn replace_wikipedia_shortcuts(text: &str, output: &mut String) {
     let result = aho_corasick_replace_all(text, &WIKIPEDIA_SHORTCUTS);
     write!(output, "{}", result).unwrap();
 }
+
+/// Replace wikipedia usernames (user/user talk pages) with pseudonym tokens, if pseudonymization is enabled.
+pub fn replace_wikipedia_usernames(text: &str, output: &mut String) {
+    let result = WIKIPEDIA_USERNAME_REGEX.replace_all(text, |caps: &Captures| {
+        match pseudonymize("USER", &caps[1].replace('_', " ")) {
+            Some(token) => format!(" {} ", token),
+            None => caps[0].to_owned(),
+        }
+    });
+    write!(output, "{}", result).unwrap();
+}
+
+/// Remove all wikipedia signatures, return new text and signers (usernames/ips, or their pseudonym tokens).
+fn find_wikipedia_signatures(text: &str) -> (String, Vec<String>) {
+    let mut result = text.to_owned();
+    let mut signers = Vec::new();
+    for regex in WIKIPEDIA_SIGNATURE_REGEXES.iter() {
+        result = regex
+            .replace_all(&result, |caps: &Captures| {
+                let signer = caps
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .next()
+                    .map(|m| m.as_str().trim().replace('_', " "))
+                    .unwrap_or_default();
+                // signatures with stripped username have no signer
+                if signer.is_empty() {
+                    return " ".to_owned();
+                }
+                let kind = match IP_ADDRESS_REGEX.is_match(&signer) {
+                    true => "IP",
+                    false => "USER",
+                };
+                signers.push(pseudonymize(kind, &signer).unwrap_or(signer));
+                " ".to_owned()
+            })
+            .into_owned();
+    }
+    (result, signers)
+}
+
+/// Remove wikipedia signatures (with timestamps, unsigned comment notes) as one unit.
+pub fn remove_wikipedia_signatures(text: &str, output: &mut String) {
+    let (result, _) = find_wikipedia_signatures(text);
+    write!(output, "{}", result).unwrap();
+}
+
+/// Find signers of wikipedia signatures, separated by comma.
+pub fn extract_wikipedia_signers(text: &str, output: &mut String) {
+    let (_, signers) = find_wikipedia_signatures(text);
+    write!(output, "{}", signers.join(",")).unwrap();
+}
+
+/// Find slang at token boundaries (not a part of urls, emails, abbreviations like U.S.).
+fn find_slang(text: &str) -> Vec<(usize, usize, &'static Slang)> {
+    SLANG_REGEX
+        .find_iter(text)
+        .filter_map(|m| {
+            let before = text[..m.start()].chars().next_back();
+            let mut after = text[m.end()..].chars();
+            let (after_first, after_second) = (after.next(), after.next());
+            if before.is_some_and(|c| ['.', '@', '/', '-', '#', '<', '_'].contains(&c))
+                || after_first.is_some_and(|c| ['@', '/', '-', '>'].contains(&c))
+                || (after_first == Some('.') && after_second.is_some_and(|c| c.is_alphanumeric()))
+            {
+                return None;
+            }
+            let word = m.as_str().replace('’', "'").to_lowercase();
+            ENGLISH_SLANG
+                .get(&word)
+                .map(|slang| (m.start(), m.end(), slang))
+        })
+        .collect()
+}
+
+/// Expand or tag slang/abbreviations (depend on SLANG_MODE), e.g. stfu => shut the fuck up
+pub fn replace_english_slang(text: &str, output: &mut String) {
+    if SLANG_MODE == SlangMode::Keep {
+        write!(output, "{}", text).unwrap();
+        return;
+    }
+    let mut last_end = 0;
+    for (start, end, slang) in find_slang(text) {
+        output.push_str(&text[last_end..start]);
+        match SLANG_MODE {
+            SlangMode::Expand => output.push_str(&slang.expansion),
+            _ => {
+                output.push_str(&text[start..end]);
+                match slang.is_profanity {
+                    true => output.push_str(" (profane slang) "),
+                    false => output.push_str(" (slang) "),
+                }
+            }
+        }
+        last_end = end;
+    }
+    output.push_str(&text[last_end..]);
+}
+
+/// Find slang/abbreviations expanding to profanity, separated by comma.
+pub fn extract_profane_slang(text: &str, output: &mut String) {
+    let result = find_slang(text)
+        .iter()
+        .filter(|(_, _, slang)| slang.is_profanity)
+        .map(|(start, end, _)| text[*start..*end].to_lowercase())
+        .collect::<Vec<String>>()
+        .join(",");
+    write!(output, "{}", result).unwrap();
+}
+
+/// Apply user-defined pattern rules (only the ones matching the original text), by priority.
+pub fn replace_pattern_rules(text: &str, output: &mut String) {
+    let matches = PATTERN_RULES.set.matches(text);
+    let mut result = text.to_owned();
+    for (index, (rule, regex)) in PATTERN_RULES.rules.iter().enumerate() {
+        if matches.matched(index) {
+            result = regex
+                .replace_all(&result, rule.replacement.as_str())
+                .into_owned();
+        }
+    }
+    write!(output, "{}", result).unwrap();
+}
@@ -0,0 +1,305 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::{env, fmt::Write, net::Ipv6Addr, str::FromStr};
+
+use crate::{is_standalone, pseudonymize};
+
+// locales of national ids, phone numbers, addresses... to detect, comma separated, e.g. en-US,en-GB
+const PII_LOCALES_ENV: &str = "PREPROCESS_PII_LOCALES";
+const PII_DEFAULT_LOCALES: &str = "en-US";
+// matches with lower confidence are kept as is
+const PII_CONFIDENCE_THRESHOLD: f32 = 0.6;
+// digit group lengths of local phone numbers, e.g. 555-123-4567 (US), 020 7946 0958 (UK)
+const PHONE_NUMBER_GROUP_LAYOUTS: [&[usize]; 5] =
+    [&[3, 3, 4], &[3, 4], &[3, 4, 4], &[5, 6], &[4, 3, 3]];
+// space separated digit groups are common in prose (page 100 1000), shorter ones need - or . separators
+const PHONE_NUMBER_MIN_SPACED_DIGITS: usize = 10;
+
+static PII_LOCALES: Lazy<Vec<String>> = Lazy::new(|| {
+    let locales = env::var(PII_LOCALES_ENV)
+        .ok()
+        .filter(|locales| !locales.trim().is_empty())
+        .unwrap_or_else(|| PII_DEFAULT_LOCALES.to_owned());
+    let locales = locales
+        .split(',')
+        .map(|locale| locale.trim().to_owned())
+        .filter(|locale| !locale.is_empty())
+        .collect::<Vec<String>>();
+    eprintln!("Pii: locales {}", locales.join(","));
+    locales
+});
+
+struct PiiPattern {
+    name: &'static str,
+    placeholder: &'static str,
+    regex: Regex,
+    // confidence of a match, 0.0 if the match is invalid (checksum, range...)
+    confidence: fn(&str) -> f32,
+    // empty means all locales
+    locales: &'static [&'static str],
+}
+
+impl PiiPattern {
+    fn new(
+        name: &'static str,
+        placeholder: &'static str,
+        regex: &str,
+        confidence: fn(&str) -> f32,
+        locales: &'static [&'static str],
+    ) -> Self {
+        PiiPattern {
+            name,
+            placeholder,
+            regex: Regex::new(regex).unwrap(),
+            confidence,
+            locales,
+        }
+    }
+}
+
+/// Ordered from most to least specific, a match is replaced before the next pattern runs.
+static PII_PATTERNS: Lazy<Vec<PiiPattern>> = Lazy::new(|| {
+    let patterns = vec![
+        PiiPattern::new(
+            "ip_address",
+            " (ip address) ",
+            r"(?i)(?:[0-9a-f]{1,4}|:)(?::[0-9a-f]{0,4}){2,7}",
+            ipv6_confidence,
+            &[],
+        ),
+        PiiPattern::new(
+            "mac_address",
+            " (mac address) ",
+            r"(?i)(?:[0-9a-f]{2}:){5}[0-9a-f]{2}|(?:[0-9a-f]{2}-){5}[0-9a-f]{2}",
+            |_| 0.9,
+            &[],
+        ),
+        PiiPattern::new(
+            "ip_address",
+            " (ip address) ",
+            r"((25[0-5]|(2[0-4]|1\d|[1-9]|)\d)\.){3}(25[0-5]|(2[0-4]|1\d|[1-9]|)\d)",
+            |_| 1.0,
+            &[],
+        ),
+        PiiPattern::new(
+            "iban",
+            " (iban) ",
+            r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,4})?\b",
+            iban_confidence,
+            &[],
+        ),
+        PiiPattern::new(
+            "credit_card",
+            " (credit card) ",
+            r"(?:\d[ -]?){12,18}\d",
+            credit_card_confidence,
+            &[],
+        ),
+        PiiPattern::new(
+            "national_id",
+            " (national id) ",
+            r"\d{3}-\d{2}-\d{4}",
+            us_ssn_confidence,
+            &["en-US"],
+        ),
+        PiiPattern::new(
+            "national_id",
+            " (national id) ",
+            r"(?i)[A-CEGHJ-PR-TW-Z]{2} ?\d{2} ?\d{2} ?\d{2} ?[A-D]",
+            |_| 0.8,
+            &["en-GB"],
+        ),
+        PiiPattern::new(
+            "national_id",
+            " (national id) ",
+            r"0\d{11}",
+            |_| 0.7,
+            &["vi-VN"],
+        ),
+        PiiPattern::new(
+            "phone_number",
+            " (phone number) ",
+            r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,4}",
+            phone_number_confidence,
+            &[],
+        ),
+        PiiPattern::new(
+            "street_address",
+            " (street address) ",
+            r"(?i)\d{1,5}(?: [a-z]+){1,3} (?:street|st|avenue|ave|road|rd|boulevard|blvd|lane|ln|drive|dr|court|ct|place|pl|way)\b\.?",
+            |_| 0.6,
+            &["en-US", "en-GB"],
+        ),
+    ];
+    patterns
+        .into_iter()
+        .filter(|pattern| {
+            pattern.locales.is_empty()
+                || pattern
+                    .locales
+                    .iter()
+                    .any(|locale| PII_LOCALES.iter().any(|enabled| enabled == locale))
+        })
+        .collect()
+});
+
+fn get_digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+fn ipv6_confidence(text: &str) -> f32 {
+    match Ipv6Addr::from_str(text) {
+        Ok(_) if text.chars().any(|c| c.is_ascii_hexdigit()) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Validate IBAN with ISO 13616 mod-97 checksum.
+fn iban_confidence(text: &str) -> f32 {
+    let iban = text.replace(' ', "");
+    if !(15..=34).contains(&iban.len()) {
+        return 0.0;
+    }
+    let (head, tail) = iban.split_at(4);
+    let mut remainder = 0;
+    for letter in tail.chars().chain(head.chars()) {
+        let value = match letter.to_digit(36) {
+            Some(value) => value,
+            None => return 0.0,
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    if remainder == 1 {
+        0.95
+    } else {
+        0.0
+    }
+}
+
+/// Validate credit card number with Luhn checksum.
+fn credit_card_confidence(text: &str) -> f32 {
+    let digits = get_digits(text);
+    if !(13..=19).contains(&digits.len()) || digits.iter().all(|digit| *digit == digits[0]) {
+        return 0.0;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| match (index % 2, digit * 2) {
+            (0, _) => *digit,
+            (_, double) if double > 9 => double - 9,
+            (_, double) => double,
+        })
+        .sum();
+    if sum.is_multiple_of(10) {
+        0.95
+    } else {
+        0.0
+    }
+}
+
+/// Validate US SSN: area is not 000/666/9xx, group is not 00, serial is not 0000.
+fn us_ssn_confidence(text: &str) -> f32 {
+    let parts = text.split('-').collect::<Vec<&str>>();
+    let area = parts[0];
+    if area == "000" || area == "666" || area.starts_with('9') || parts[1] == "00" {
+        return 0.0;
+    }
+    if parts[2] == "0000" {
+        return 0.0;
+    }
+    0.85
+}
+
+/// Phone numbers with country code or separators are more likely than plain digits.
+fn phone_number_confidence(text: &str) -> f32 {
+    static DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(\d{4}[-./]\d{1,2}[-./]\d{1,2}|\d{1,2}[-./]\d{1,2}[-./]\d{2,4})$").unwrap()
+    });
+    let digits_len = get_digits(text).len();
+    if !(7..=15).contains(&digits_len) || DATE_REGEX.is_match(text) {
+        return 0.0;
+    }
+    // separated digits are only a phone number with a typical layout, not e.g. 2005 2006
+    let groups = text
+        .split([' ', '-', '.'])
+        .map(|group| group.len())
+        .collect::<Vec<usize>>();
+    if text.starts_with('+') {
+        0.9
+    } else if text.starts_with('(') {
+        0.8
+    } else if PHONE_NUMBER_GROUP_LAYOUTS.contains(&groups.as_slice())
+        && (text.contains(['-', '.']) || digits_len >= PHONE_NUMBER_MIN_SPACED_DIGITS)
+    {
+        0.7
+    } else {
+        0.5
+    }
+}
+
+/// Replace all PII, return new text and found PII with confidence.
+fn find_pii(text: &str) -> (String, Vec<(&'static str, f32)>) {
+    let mut result = text.to_owned();
+    let mut found = Vec::new();
+    for pattern in PII_PATTERNS.iter() {
+        let haystack = result.clone();
+        result = pattern
+            .regex
+            .replace_all(&haystack, |caps: &Captures| {
+                let m = caps.get(0).unwrap();
+                let mut end = m.end();
+                let mut confidence = (pattern.confidence)(m.as_str());
+                // an iban can be followed by a word of its group layout, e.g. ... 3212 THAT,
+                // shorter prefixes (up to a space) are tried
+                if pattern.name == "iban" {
+                    while confidence < PII_CONFIDENCE_THRESHOLD {
+                        match haystack[m.start()..end].rfind(' ') {
+                            Some(index) => {
+                                end = m.start() + index;
+                                confidence = (pattern.confidence)(&haystack[m.start()..end]);
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                if confidence < PII_CONFIDENCE_THRESHOLD
+                    || !is_standalone(&haystack, m.start(), end)
+                {
+                    return m.as_str().to_owned();
+                }
+                found.push((pattern.name, confidence));
+                let rest = &haystack[end..m.end()];
+                if pattern.name == "ip_address" {
+                    if let Some(token) = pseudonymize("IP", m.as_str()) {
+                        return format!(" {} {}", token, rest);
+                    }
+                }
+                format!("{}{}", pattern.placeholder, rest)
+            })
+            .into_owned();
+    }
+    (result, found)
+}
+
+/// Filter out all PII (ip/mac addresses, credit cards, IBANs, national ids, phone numbers, street addresses).
+pub fn replace_pii(text: &str, output: &mut String) {
+    let (result, _) = find_pii(text);
+    write!(output, "{}", result).unwrap();
+}
+
+/// Find all PII kinds with confidence, e.g. phone_number:0.90,credit_card:0.95
+pub fn extract_pii(text: &str, output: &mut String) {
+    let (_, found) = find_pii(text);
+    let result = found
+        .iter()
+        .map(|(name, confidence)| format!("{}:{:.2}", name, confidence))
+        .collect::<Vec<String>>()
+        .join(",");
+    write!(output, "{}", result).unwrap();
+}
@@ -0,0 +1,50 @@
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::Sha256;
+use std::{env, fmt::Write};
+
+// secret key of keyed hash, pseudonymization is disabled if it is not set
+const PSEUDONYM_KEY_ENV: &str = "PREPROCESS_PSEUDONYM_KEY";
+// number of hash bytes in token, e.g. 4 => <IP_3fa9c2d1>
+const PSEUDONYM_HASH_BYTES: usize = 4;
+
+static PSEUDONYM_KEY: Lazy<Option<Vec<u8>>> = Lazy::new(|| {
+    let key = env::var(PSEUDONYM_KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty());
+    if key.is_some() {
+        eprintln!("Pseudonyms: keyed pseudonymization enabled");
+    }
+    key.map(|key| key.into_bytes())
+});
+
+static PSEUDONYM_TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)^<?(ip|email|url|user)_[0-9a-f]{{{}}}>?$",
+        PSEUDONYM_HASH_BYTES * 2
+    ))
+    .unwrap()
+});
+
+/// Create stable token from keyed hash (HMAC-SHA256) of value, e.g. (IP, 1.2.3.4) => <IP_3fa9c2d1>
+/// Return None if pseudonymization is disabled.
+pub fn pseudonymize(kind: &str, value: &str) -> Option<String> {
+    let key = PSEUDONYM_KEY.as_ref()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(kind.as_bytes());
+    mac.update(b":");
+    mac.update(value.trim().to_lowercase().as_bytes());
+    let hash = mac.finalize().into_bytes();
+    let mut token = format!("<{}_", kind);
+    for byte in hash.iter().take(PSEUDONYM_HASH_BYTES) {
+        write!(token, "{:02x}", byte).unwrap();
+    }
+    token.push('>');
+    Some(token)
+}
+
+/// Check if a word is a pseudonym token, with or without angle brackets.
+pub fn is_pseudonym(word: &str) -> bool {
+    PSEUDONYM_TOKEN_REGEX.is_match(word)
+}
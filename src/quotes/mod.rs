@@ -0,0 +1,125 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt::Write;
+
+// markers around quoted spans, plain words so they survive the next stages
+pub const QUOTE_START_MARKER: &str = "quotestart";
+pub const QUOTE_END_MARKER: &str = "quoteend";
+
+#[derive(PartialEq)]
+pub enum QuoteMode {
+    /// Keep quoted spans between markers.
+    Tag,
+    /// Remove quoted spans from text (they are still in the quoted column).
+    Move,
+    /// Keep quoted spans between markers, the spelling corrector drops markers and skips profanity matching inside.
+    Exclude,
+}
+
+pub const QUOTE_MODE: QuoteMode = QuoteMode::Exclude;
+
+// quoted spans with the content in `quote` group, text in `before`/`after` groups is kept, from most to least specific
+static QUOTE_REGEXES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // <blockquote>you are ...</blockquote>
+        r"(?is)<blockquote\b[^>]*>(?P<quote>.*?)</blockquote\s*>",
+        // Foo wrote: you are ... (until blank line)
+        r"(?is)(?P<before>\bwrote:)[ \t]*\n?(?P<quote>.+?)(?P<after>\n[ \t]*\n|$)",
+        // > you are ... (not a >!spoiler!<)
+        r"(?m)^[ \t]*>(?P<quote>(?:[^!\n].*)?(?:\n[ \t]*>(?:[^!\n].*)?)*)",
+        r#""(?P<quote>[^"\n]{1,300})""#,
+        r"“(?P<quote>[^”\n]{1,300})”",
+        r"(?P<before>^|[\s(\[])'(?P<quote>[^'\n]{1,300}?)'(?P<after>$|[\s.,!?;:)\]])",
+    ]
+    .iter()
+    .map(|regex| Regex::new(regex).unwrap())
+    .collect()
+});
+// html tags, quotes inside them are attribute values, e.g. <a href="http://example.com">
+static HTML_TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^<>]*>").unwrap());
+static QUOTE_PREFIX_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*>[ \t]?").unwrap());
+
+struct Quote<'a> {
+    start: usize,
+    end: usize,
+    before: &'a str,
+    quote: &'a str,
+    after: &'a str,
+}
+
+/// Find quoted spans (not overlapping, the first pattern wins).
+fn find_quotes(text: &str) -> Vec<Quote<'_>> {
+    let mut found: Vec<Quote> = Vec::new();
+    let tags = HTML_TAG_REGEX
+        .find_iter(text)
+        .map(|tag| (tag.start(), tag.end()))
+        .collect::<Vec<(usize, usize)>>();
+    let is_in_tag = |position: usize| {
+        tags.iter()
+            .any(|(start, end)| *start < position && position < *end)
+    };
+    for regex in QUOTE_REGEXES.iter() {
+        for caps in regex.captures_iter(text) {
+            let span = caps.get(0).unwrap();
+            let quote = caps.name("quote").unwrap().as_str();
+            if quote.trim().is_empty()
+                || is_in_tag(span.start())
+                || is_in_tag(span.end())
+                || found
+                    .iter()
+                    .any(|other| span.start() < other.end && other.start < span.end())
+            {
+                continue;
+            }
+            found.push(Quote {
+                start: span.start(),
+                end: span.end(),
+                before: caps.name("before").map_or("", |m| m.as_str()),
+                quote,
+                after: caps.name("after").map_or("", |m| m.as_str()),
+            });
+        }
+    }
+    found.sort_by_key(|quote| quote.start);
+    found
+}
+
+/// Get clean content of quoted span, e.g. "> you\n> are" => "you\nare"
+fn get_quote_content(content: &str) -> String {
+    QUOTE_PREFIX_REGEX
+        .replace_all(content, "")
+        .trim()
+        .to_owned()
+}
+
+/// Tag quoted spans with markers, or remove them (depend on QUOTE_MODE).
+pub fn replace_quotes(text: &str, output: &mut String) {
+    let mut last_end = 0;
+    for quote in find_quotes(text) {
+        output.push_str(&text[last_end..quote.start]);
+        output.push_str(quote.before);
+        if QUOTE_MODE != QuoteMode::Move {
+            write!(
+                output,
+                " {} {} {} ",
+                QUOTE_START_MARKER,
+                get_quote_content(quote.quote),
+                QUOTE_END_MARKER
+            )
+            .unwrap();
+        }
+        output.push_str(quote.after);
+        last_end = quote.end;
+    }
+    output.push_str(&text[last_end..]);
+}
+
+/// Find quoted spans, separated by " | ".
+pub fn extract_quotes(text: &str, output: &mut String) {
+    let result = find_quotes(text)
+        .iter()
+        .map(|quote| get_quote_content(quote.quote))
+        .collect::<Vec<String>>()
+        .join(" | ");
+    write!(output, "{}", result).unwrap();
+}
@@ -0,0 +1,121 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write,
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+// folders with a manifest.json: dictionaries of each language, then other data loaded by the stages
+const RESOURCE_MANIFEST_DIRPATHS: [&str; 4] = [
+    "data/dictionaries/english",
+    "data/dictionaries/vietnamese",
+    "data/others",
+    "data/emojis",
+];
+
+/// Data file of a folder, its capabilities are degraded if it is missing or invalid.
+#[derive(Deserialize)]
+struct ResourceFile {
+    path: String,
+    required: bool,
+    capabilities: Vec<String>,
+    #[serde(default)]
+    min_lines: usize,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResourceManifest {
+    // language of dictionaries, or kind of data, e.g. english, others
+    name: String,
+    files: Vec<ResourceFile>,
+}
+
+enum ResourceStatus {
+    Ok,
+    Missing,
+    TooShort(usize),
+    ChecksumMismatch(String),
+}
+
+/// Check a data file: existence, line count, then checksum (if given).
+fn check_resource_file(dirpath: &Path, resource: &ResourceFile) -> ResourceStatus {
+    let content = match fs::read(dirpath.join(&resource.path)) {
+        Ok(content) => content,
+        Err(_) => return ResourceStatus::Missing,
+    };
+    let line_count = content
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .count();
+    if line_count < resource.min_lines {
+        return ResourceStatus::TooShort(line_count);
+    }
+    if let Some(expected) = &resource.sha256 {
+        let mut checksum = String::new();
+        for byte in Sha256::digest(&content).iter() {
+            write!(checksum, "{:02x}", byte).unwrap();
+        }
+        if !checksum.eq_ignore_ascii_case(expected) {
+            return ResourceStatus::ChecksumMismatch(checksum);
+        }
+    }
+    ResourceStatus::Ok
+}
+
+/// Check all manifests, return the report, if all required files are valid
+/// and the degraded capabilities, e.g. english: slang expansion, others: pattern rules
+pub fn check_resources() -> (String, bool, Vec<String>) {
+    let mut report = String::new();
+    let mut is_complete = true;
+    let mut degraded = Vec::new();
+    for dirpath in RESOURCE_MANIFEST_DIRPATHS {
+        let dirpath = Path::new(dirpath);
+        let manifest: ResourceManifest = match File::open(dirpath.join("manifest.json")) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)).unwrap(),
+            Err(_) => {
+                writeln!(report, "{}: manifest missing", dirpath.display()).unwrap();
+                is_complete = false;
+                continue;
+            }
+        };
+        writeln!(report, "{}:", manifest.name).unwrap();
+        let mut degraded_capabilities = Vec::new();
+        for resource in manifest.files.iter() {
+            let status = match check_resource_file(dirpath, resource) {
+                ResourceStatus::Ok => {
+                    writeln!(report, "  ok       {}", resource.path).unwrap();
+                    continue;
+                }
+                ResourceStatus::Missing => "missing".to_owned(),
+                ResourceStatus::TooShort(line_count) => format!(
+                    "too short ({} lines, expected at least {})",
+                    line_count, resource.min_lines
+                ),
+                ResourceStatus::ChecksumMismatch(checksum) => {
+                    format!("checksum mismatch (sha256 {})", checksum)
+                }
+            };
+            let level = if resource.required {
+                "error"
+            } else {
+                "warning"
+            };
+            writeln!(report, "  {:<8} {}: {}", level, resource.path, status).unwrap();
+            is_complete &= !resource.required;
+            degraded_capabilities.extend(resource.capabilities.iter().cloned());
+        }
+        if !degraded_capabilities.is_empty() {
+            writeln!(report, "  degraded: {}", degraded_capabilities.join(", ")).unwrap();
+            degraded.extend(
+                degraded_capabilities
+                    .iter()
+                    .map(|capability| format!("{}: {}", manifest.name, capability)),
+            );
+        }
+    }
+    (report, is_complete, degraded)
+}
@@ -0,0 +1,71 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::fmt::Write;
+
+use crate::{pseudonymize, SYMSPELL};
+
+// hashtags are split into known words only, misspellings are left to the spelling corrector
+const HASHTAG_SEGMENTATION_MAX_EDIT_DISTANCE: i64 = 0;
+
+// mentions (@name, u/name) in `user` group, subreddits (r/name) in `subreddit` group
+static MENTION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?P<prefix>^|[^\w@/.])(?:@(?P<user>\w{2,30})|/?u/(?P<reddit_user>[\w-]{3,20})|/?r/(?P<subreddit>\w{2,21}))\b")
+        .unwrap()
+});
+static HASHTAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?P<prefix>^|[^\w&#/])#(?P<hashtag>[A-Za-z_]\w*)").unwrap());
+static CAMEL_CASE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Z]+[a-z]*|[a-z]+|\d+").unwrap());
+
+/// Get mentioned user, or its pseudonym token if pseudonymization is enabled.
+fn get_mentioned_user(caps: &Captures) -> Option<String> {
+    let user = caps.name("user").or(caps.name("reddit_user"))?.as_str();
+    Some(pseudonymize("USER", user).unwrap_or(user.to_owned()))
+}
+
+/// Replace mentions with placeholders (or pseudonym tokens) and subreddits with placeholders.
+pub fn replace_mentions(text: &str, output: &mut String) {
+    let result = MENTION_REGEX.replace_all(text, |caps: &Captures| {
+        let replacement = if caps.name("subreddit").is_some() {
+            " (subreddit) ".to_owned()
+        } else {
+            match get_mentioned_user(caps).filter(|user| user.starts_with('<')) {
+                Some(token) => format!(" {} ", token),
+                None => " (mention) ".to_owned(),
+            }
+        };
+        format!("{}{}", &caps["prefix"], replacement)
+    });
+    write!(output, "{}", result).unwrap();
+}
+
+/// Find mentioned users (or their pseudonym tokens), separated by comma.
+pub fn extract_mentions(text: &str, output: &mut String) {
+    let result = MENTION_REGEX
+        .captures_iter(text)
+        .filter_map(|caps| get_mentioned_user(&caps))
+        .collect::<Vec<String>>()
+        .join(",");
+    write!(output, "{}", result).unwrap();
+}
+
+/// Split hashtags into words, e.g. #youarestupid => you are stupid, #YouAreStupid => you are stupid
+pub fn split_hashtags(text: &str, output: &mut String) {
+    let result = HASHTAG_REGEX.replace_all(text, |caps: &Captures| {
+        let hashtag = caps["hashtag"].replace('_', " ");
+        let words = CAMEL_CASE_REGEX
+            .find_iter(&hashtag)
+            .map(|word| {
+                SYMSPELL
+                    .word_segmentation(
+                        &word.as_str().to_lowercase(),
+                        HASHTAG_SEGMENTATION_MAX_EDIT_DISTANCE,
+                    )
+                    .segmented_string
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        format!("{} {} ", &caps["prefix"], words)
+    });
+    write!(output, "{}", result).unwrap();
+}
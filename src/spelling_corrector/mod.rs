@@ -1,19 +1,30 @@
 use std::{
     borrow::Borrow,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
+    time::{Duration, Instant},
 };
 
 use aho_corasick::{AhoCorasick, MatchKind};
 use once_cell::sync::Lazy;
+use regex::Regex;
 use rust_stemmers::{Algorithm, Stemmer};
-use symspell::{SymSpell, UnicodeStringStrategy, Verbosity};
+use serde::Deserialize;
+use symspell::{Suggestion, SymSpell, UnicodeStringStrategy, Verbosity};
 
-use crate::get_unicode_category;
+use crate::{
+    get_unicode_category, is_pseudonym, is_standalone, QuoteMode, QUOTE_END_MARKER, QUOTE_MODE,
+    QUOTE_START_MARKER,
+};
 
-const BIGRAM_DUPLICATE_THRESHOLD: f32 = 0.3; // magic number
+// placeholder of protected terms between masking and restoring, followed by letters of its index
+const PROTECTED_TERM_MARKER: &str = "protectedterm";
+// runs of a letter this long are elongation, e.g. sooo (English has no triple letters)
+const ELONGATION_MIN_RUN_LENGTH: usize = 3;
+// repeated letter runs tried both ways (1 or 2 letters), the others are reduced to 2 letters
+const ELONGATION_MAX_RUNS: usize = 8;
 
 // const ENGLISH_ONE_LETTER_WORDS: [char; 12] =
 //     ['a', 'i', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
@@ -23,19 +34,165 @@ const BIGRAM_DUPLICATE_THRESHOLD: f32 = 0.3; // magic number
 // ];
 // const ENGLISH_AVG_WORD_LENGTH: i32 = 5;
 // const ENGLISH_WORD_LENGTH_THRESHOLD: i32 = 2 * ENGLISH_AVG_WORD_LENGTH;
-const ENGLISH_SEGMENTATION_MAX_EDIT_DISTANCE: i64 = 2;
+
+/// Correction parameters scaled by token length, SymSpell dictionaries support edit distances up to 2.
+pub struct CorrectionPolicy {
+    /// Max edit distance by token length: (max length, distance), the last bucket covers longer tokens
+    pub max_edit_distances: &'static [(usize, i64)],
+    /// Shorter tokens are kept as is, e.g. u, ur
+    pub min_correction_length: usize,
+    /// Longer tokens are not segmented (too slow, and rarely words)
+    pub max_segmentation_length: usize,
+    /// Max edit distance of each segmented word
+    pub segmentation_max_edit_distance: i64,
+}
+
+#[allow(dead_code)]
+impl CorrectionPolicy {
+    pub const ENGLISH: CorrectionPolicy = CorrectionPolicy {
+        max_edit_distances: &[(2, 0), (4, 1), (usize::MAX, 2)],
+        min_correction_length: 3,
+        max_segmentation_length: 40,
+        segmentation_max_edit_distance: 2,
+    };
+    // diacritics make words shorter and closer to each other
+    pub const VIETNAMESE: CorrectionPolicy = CorrectionPolicy {
+        max_edit_distances: &[(2, 0), (6, 1), (usize::MAX, 2)],
+        min_correction_length: 2,
+        max_segmentation_length: 30,
+        segmentation_max_edit_distance: 1,
+    };
+
+    /// Get max edit distance of a token by its length.
+    pub fn max_edit_distance(&self, word: &str) -> i64 {
+        let length = word.chars().count();
+        self.max_edit_distances
+            .iter()
+            .find(|(max_length, _)| length <= *max_length)
+            .map_or(0, |(_, distance)| *distance)
+    }
+}
+
+pub const CORRECTION_POLICY: CorrectionPolicy = CorrectionPolicy::ENGLISH;
+// corrections less confident than this keep the original token
+const CORRECTION_CONFIDENCE_THRESHOLD: f64 = 0.5;
+// average log10-probability of segmented words, from nonsense (unknown parts) to common words
+const SEGMENTATION_MIN_LOG_PROBABILITY: f64 = -10.0;
+const SEGMENTATION_MAX_LOG_PROBABILITY: f64 = -4.0;
+// a word this close is preferred to a less likely segmentation, e.g. recieve => receive, not rec i eve
+const CLOSEST_WORD_MAX_EDIT_DISTANCE: i64 = 1;
+// log10 penalty of an edit: as unlikely as a 10 times less frequent word
+const CLOSEST_WORD_EDIT_DISTANCE_PENALTY: f64 = 1.0;
+// also emit original/corrected tokens and correction trace columns (for training)
+pub const EMIT_CORRECTION_TOKENS: bool = false;
+// time budget of a row, unknown words after it are passed through (e.g. pathological comments)
+const PROCESS_TEXT_TIME_BUDGET: Duration = Duration::from_millis(500);
+// sound-alike swear words count this many times more than general words, e.g. phuck => fuck
+const PROFANITY_FREQUENCY_WEIGHT: f64 = 10.0;
+// general words this frequent win ties with less frequent profane terms, e.g. fak => far, not fat
+const PROFANITY_COMMON_WORD_MIN_COUNT: i64 = 1_000_000;
+// shorter parts of a compound are not words, e.g. sockpuppet => sock puppet, but a+ss
+const COMPOUND_MIN_PART_LENGTH: usize = 3;
+// phonetic matches are less certain than edit distance matches
+const PHONETIC_MATCH_CONFIDENCE: f64 = 0.6;
+const PHONETIC_MIN_KEY_LENGTH: usize = 2;
+const ENGLISH_FREQUENCY_FILEPATH: &str =
+    "data/dictionaries/english/frequency_dictionary_en_82_765.txt";
+const ENGLISH_BIGRAM_FREQUENCY_FILEPATH: &str =
+    "data/dictionaries/english/frequency_bigramdictionary_en_243_342.txt";
+// supplemental dictionary learned from a corpus by `learn-vocab` command, optional
+pub const ENGLISH_LEARNED_VOCABULARY_FILEPATH: &str =
+    "data/dictionaries/english/learned_vocabulary.txt";
+
+#[allow(dead_code)]
+#[derive(PartialEq)]
+pub enum CorrectionMode {
+    /// Correct each unknown word on its own, by unigram frequency.
+    Unigram,
+    /// Correct words of a sentence together, by bigram probability (Unigram without bigram dictionary).
+    Context,
+}
+
+pub const CORRECTION_MODE: CorrectionMode = CorrectionMode::Context;
+const CONTEXT_MAX_CANDIDATES: usize = 8;
+// log-probability penalties: an edit (or a confused word) is as unlikely as a 10 times less frequent word
+const CONTEXT_EDIT_DISTANCE_PENALTY: f64 = std::f64::consts::LN_10;
+const CONTEXT_CONFUSION_PENALTY: f64 = std::f64::consts::LN_10;
+// stupid backoff, from bigram to unigram probability
+const CONTEXT_BACKOFF: f64 = 0.4;
+// real words commonly confused with each other, e.g. "your an idiot"
+const ENGLISH_CONFUSION_SETS: [&[&str]; 7] = [
+    &["their", "there", "they are"],
+    &["your", "you are"],
+    &["its", "it is"],
+    &["then", "than"],
+    &["to", "too"],
+    &["lose", "loose"],
+    &["were", "where"],
+];
 
 pub static SYMSPELL: Lazy<SymSpell<UnicodeStringStrategy>> = Lazy::new(|| {
     eprintln!("Spelling Corrector: SymSpell loading...");
     let mut spell = SymSpell::default();
-    let english_frequency_filepath = "data/dictionaries/english/frequency_dictionary_en_82_765.txt";
-    let english_bigram_frequency_filepath =
-        "data/dictionaries/english/frequency_bigramdictionary_en_243_342.txt";
-    spell.load_dictionary(&english_frequency_filepath, 0, 1, " ");
-    spell.load_bigram_dictionary(&english_bigram_frequency_filepath, 0, 2, " ");
+    spell.load_dictionary(ENGLISH_FREQUENCY_FILEPATH, 0, 1, " ");
+    if spell.load_dictionary(ENGLISH_LEARNED_VOCABULARY_FILEPATH, 0, 1, " ") {
+        eprintln!("Spelling Corrector: English learned vocabulary loaded");
+    }
+    if !spell.load_bigram_dictionary(ENGLISH_BIGRAM_FREQUENCY_FILEPATH, 0, 2, " ") {
+        eprintln!(
+            "Spelling Corrector: English bigrams missing, word segmentation uses unigrams only"
+        );
+    }
     spell
 });
 
+static ENGLISH_UNIGRAMS: Lazy<(HashMap<String, u64>, u64)> = Lazy::new(|| {
+    eprintln!("Spelling Corrector: English unigrams loading...");
+    let mut unigrams = HashMap::new();
+    for filepath in [
+        ENGLISH_FREQUENCY_FILEPATH,
+        ENGLISH_LEARNED_VOCABULARY_FILEPATH,
+    ] {
+        let file = match File::open(filepath) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        let reader = BufReader::new(file);
+        for line in reader.lines() {
+            let line = line.unwrap();
+            if let Some((word, count)) = line.split_once(' ') {
+                unigrams
+                    .entry(word.to_owned())
+                    .or_insert(count.trim().parse().unwrap_or(0));
+            }
+        }
+    }
+    let total = unigrams.values().sum();
+    (unigrams, total)
+});
+
+static ENGLISH_BIGRAMS: Lazy<HashMap<String, u64>> = Lazy::new(|| {
+    eprintln!("Spelling Corrector: English bigrams loading...");
+    let mut bigrams = HashMap::new();
+    let file = match File::open(ENGLISH_BIGRAM_FREQUENCY_FILEPATH) {
+        Ok(file) => file,
+        Err(_) => {
+            eprintln!("Spelling Corrector: English bigrams missing, context correction disabled");
+            return bigrams;
+        }
+    };
+    let reader = BufReader::new(file);
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let parts = line.split(' ').collect::<Vec<&str>>();
+        if parts.len() >= 3 {
+            let bigram = format!("{} {}", parts[0], parts[1]);
+            bigrams.insert(bigram, parts[2].trim().parse().unwrap_or(0));
+        }
+    }
+    bigrams
+});
+
 static ENGLISH_DICTIONARY: Lazy<HashSet<String>> = Lazy::new(|| {
     eprintln!("Spelling Corrector: English dictionary loading...");
     let mut dictionary: HashSet<String> = HashSet::new();
@@ -45,6 +202,14 @@ static ENGLISH_DICTIONARY: Lazy<HashSet<String>> = Lazy::new(|| {
     for line in reader.lines() {
         dictionary.insert(line.unwrap());
     }
+    if let Ok(file) = File::open(ENGLISH_LEARNED_VOCABULARY_FILEPATH) {
+        let reader = BufReader::new(file);
+        for line in reader.lines() {
+            let line = line.unwrap();
+            let word = line.split(' ').next().unwrap_or_default();
+            dictionary.insert(word.to_owned());
+        }
+    }
     dictionary
 });
 
@@ -61,6 +226,37 @@ static ENGLISH_SWEAR_WORDS: Lazy<Vec<String>> = Lazy::new(|| {
     swear_words
 });
 
+/// Profanity lexicon as a secondary dictionary, weighted by the frequency of its words.
+static PROFANITY_SYMSPELL: Lazy<SymSpell<UnicodeStringStrategy>> = Lazy::new(|| {
+    eprintln!("Spelling Corrector: profanity SymSpell loading...");
+    let mut spell = SymSpell::default();
+    for swear_word in ENGLISH_SWEAR_WORDS.iter() {
+        let count = ENGLISH_UNIGRAMS.0.get(swear_word).copied().unwrap_or(1);
+        spell.load_dictionary_line(&format!("{}\t{}", swear_word, count), 0, 1, "\t");
+    }
+    spell
+});
+
+/// Phonetic keys of swear words, with the most frequent swear word of each key, e.g. FK => fuck
+static PROFANITY_PHONETIC_INDEX: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    eprintln!("Spelling Corrector: profanity phonetic index creating...");
+    let mut index: HashMap<String, String> = HashMap::new();
+    let get_count = |word: &str| ENGLISH_UNIGRAMS.0.get(word).copied().unwrap_or(0);
+    for swear_word in ENGLISH_SWEAR_WORDS.iter() {
+        let key = get_phonetic_key(swear_word);
+        if key.len() < PHONETIC_MIN_KEY_LENGTH {
+            continue;
+        }
+        match index.get(&key) {
+            Some(indexed) if get_count(indexed) >= get_count(swear_word) => {}
+            _ => {
+                index.insert(key, swear_word.to_owned());
+            }
+        }
+    }
+    index
+});
+
 static ENGLISH_SWEAR_WORDS_REPLACEMENT: Lazy<Vec<String>> = Lazy::new(|| {
     eprintln!("Spelling Corrector: English swear words replacement creating...");
     ENGLISH_SWEAR_WORDS
@@ -94,71 +290,130 @@ static ENGLISH_FIRSTNAMES_REPLACEMENT: Lazy<Vec<String>> = Lazy::new(|| {
         .collect()
 });
 
+/// User-supplied term never altered by the corrector, `term` is matched against the whole token.
+#[derive(Deserialize)]
+struct ProtectedTerm {
+    term: String,
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+    reason: String,
+}
+
+struct ProtectedTerms {
+    // exact terms, case-insensitive ones are lowercased
+    exact: HashMap<String, String>,
+    exact_insensitive: HashMap<String, String>,
+    regexes: Vec<(Regex, String)>,
+}
+
+static PROTECTED_TERMS: Lazy<ProtectedTerms> = Lazy::new(|| {
+    eprintln!("Spelling Corrector: protected terms loading...");
+    let protected_terms_filepath = "data/others/protected_terms.json";
+    let file = File::open(protected_terms_filepath).unwrap();
+    let reader = BufReader::new(file);
+    let terms: Vec<ProtectedTerm> = serde_json::from_reader(reader).unwrap();
+    let mut protected_terms = ProtectedTerms {
+        exact: HashMap::new(),
+        exact_insensitive: HashMap::new(),
+        regexes: Vec::new(),
+    };
+    for term in terms {
+        match (term.regex, term.case_sensitive) {
+            (true, case_sensitive) => {
+                let flags = if case_sensitive { "" } else { "(?i)" };
+                let regex = Regex::new(&format!("{}^(?:{})$", flags, term.term))
+                    .unwrap_or_else(|error| panic!("Protected term {}: {}", term.term, error));
+                protected_terms.regexes.push((regex, term.reason));
+            }
+            (false, true) => {
+                protected_terms.exact.insert(term.term, term.reason);
+            }
+            (false, false) => {
+                protected_terms
+                    .exact_insensitive
+                    .insert(term.term.to_lowercase(), term.reason);
+            }
+        }
+    }
+    protected_terms
+});
+
 static ENGLISH_STEMMER: Lazy<Stemmer> = Lazy::new(|| {
     eprintln!("Spelling Corrector: English stemmer loading...");
     Stemmer::create(Algorithm::English)
 });
 
-/// Create vector of bigrams, e.g. hello => he,el,ll,lo
-fn split_bigram(word: &str) -> Vec<[char; 2]> {
-    //* Vietnamese and English do not need graphemes
-    // // let letters = word.graphemes(true).collect::<Vec<&str>>();
-    // // let letters0 = letters.split_last().unwrap().1;
-    // // let letters1 = letters.split_first().unwrap().1;
-    let letters = word.chars().into_iter().collect::<Vec<char>>();
-    let letters0 = letters.split_last().unwrap().1;
-    let letters1 = letters.split_first().unwrap().1;
-    let mut bigrams = Vec::new();
-    for (letter0, letter1) in letters0.iter().zip(letters1) {
-        let bigram = [*letter0, *letter1];
-        bigrams.push(bigram);
+/// Split a word into runs of the same letter, e.g. hello => (h,1),(e,1),(l,2),(o,1)
+fn split_runs(word: &str) -> Vec<(char, usize)> {
+    let mut runs: Vec<(char, usize)> = Vec::new();
+    for letter in word.chars() {
+        match runs.last_mut() {
+            Some((last, length)) if *last == letter => *length += 1,
+            _ => runs.push((letter, 1)),
+        }
     }
-    bigrams
+    runs
 }
 
-/// Create string from bigrams, e.g. he,el,ll,lo => hello
-fn join_bigram(bigrams: &Vec<[char; 2]>) -> String {
-    let mut new_word = String::new();
-    for bigram in bigrams.iter() {
-        new_word.push(bigram[0]);
-    }
-    new_word.push(bigrams.last().unwrap()[1]);
-    new_word
+/// Check if a word is elongated (a letter repeated for emphasis), e.g. sooo, fuuuck
+fn is_elongated(word: &str) -> bool {
+    split_runs(word)
+        .iter()
+        .any(|(letter, length)| letter.is_alphabetic() && *length >= ELONGATION_MIN_RUN_LENGTH)
 }
 
-/// Remove duplicate bigrams, e.g. helllo => he,el,ll,ll,lo => he,el,ll,lo => hello
-/// If there are too many duplicate bigrams in a word, remove all duplicate bigrams, e.g. hheelloo => helo
-fn reduce_bigram(word: &str) -> String {
-    let bigrams = split_bigram(word);
-    let mut new_bigrams: Vec<[char; 2]> = Vec::new();
-    // keep first group of duplicate bigram
-    let mut repeat_flag = false;
-    let mut duplication_num = 0;
-    for bigram in bigrams.iter() {
-        if bigram[0] == bigram[1] {
-            if repeat_flag {
-                continue;
-            }
-            repeat_flag = true;
-            duplication_num += 1;
-        } else {
-            repeat_flag = false;
+/// Normalize elongation: try each repeated letter run reduced to 1 or 2 letters, and keep the
+/// most frequent known word, e.g. hheelloo => hello, gooood => good, cooool => cool
+/// If no candidate is known, elongated runs are reduced to 1 letter, e.g. youuuareee => youare
+fn normalize_elongation(word: &str) -> String {
+    let runs = split_runs(word);
+    let repeated = runs
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, length))| *length >= 2)
+        .map(|(index, _)| index)
+        .take(ELONGATION_MAX_RUNS)
+        .collect::<Vec<usize>>();
+    let fallback = runs
+        .iter()
+        .map(
+            |(letter, length)| match *length >= ELONGATION_MIN_RUN_LENGTH {
+                true => letter.to_string(),
+                false => letter.to_string().repeat(*length),
+            },
+        )
+        .collect::<String>();
+    if repeated.is_empty() {
+        return fallback;
+    }
+    let mut best: Option<(String, u64)> = None;
+    // each bit of mask reduces a repeated run to 1 letter
+    for mask in 0..(1usize << repeated.len()) {
+        let candidate = runs
+            .iter()
+            .enumerate()
+            .map(|(index, (letter, length))| {
+                let reduced = match repeated.iter().position(|repeated| *repeated == index) {
+                    Some(bit) if mask & (1 << bit) != 0 => 1,
+                    _ => (*length).min(2),
+                };
+                letter.to_string().repeat(reduced)
+            })
+            .collect::<String>();
+        if !is_known_word(&candidate) {
+            continue;
         }
-        new_bigrams.push(*bigram);
-    }
-    // check if it have too many duplicate bigrams
-    let len_bigrams = new_bigrams.len() + 1;
-    if len_bigrams > 3 && duplication_num as f32 > len_bigrams as f32 * BIGRAM_DUPLICATE_THRESHOLD {
-        let mut new_new_bigrams: Vec<[char; 2]> = Vec::new();
-        for bigram in new_bigrams.iter() {
-            if bigram[0] == bigram[1] {
-                continue;
-            }
-            new_new_bigrams.push(*bigram);
+        let count = ENGLISH_UNIGRAMS.0.get(&candidate).copied().unwrap_or(0);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_count)| count > *best_count)
+        {
+            best = Some((candidate, count));
         }
-        return join_bigram(&new_new_bigrams);
     }
-    join_bigram(&new_bigrams)
+    best.map(|(candidate, _)| candidate).unwrap_or(fallback)
 }
 
 /// Check if a word is in the corpora.
@@ -166,12 +421,95 @@ fn is_in_corpora(word: &str) -> bool {
     ENGLISH_DICTIONARY.contains(word)
 }
 
+/// Find protected terms among standalone words (not in links, hashtags or mentions), e.g. see GitHub. => GitHub
+fn find_protected_terms(text: &str) -> Vec<regex::Match<'_>> {
+    static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\w+").unwrap());
+    WORD_REGEX
+        .find_iter(text)
+        .filter(|m| {
+            is_standalone(text, m.start(), m.end())
+                && !text[..m.start()].ends_with(['#', '@', '/'])
+                && get_protection_reason(m.as_str()).is_some()
+        })
+        .collect()
+}
+
+/// Get placeholder of the n-th protected term, letters only to pass every stage, e.g. 1 => protectedtermb
+fn get_protected_term_placeholder(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut index = index;
+    loop {
+        letters.push((b'a' + (index % 26) as u8) as char);
+        index /= 26;
+        if index == 0 {
+            break;
+        }
+    }
+    format!(
+        "{}{}",
+        PROTECTED_TERM_MARKER,
+        letters.iter().rev().collect::<String>()
+    )
+}
+
+/// Find protected terms of a text (space-separated, in order), restored by `restore_protected_terms`.
+pub fn extract_protected_terms(text: &str, output: &mut String) {
+    let terms = find_protected_terms(text)
+        .iter()
+        .map(|m| m.as_str())
+        .collect::<Vec<&str>>();
+    write!(output, "{}", terms.join(" ")).unwrap();
+}
+
+/// Replace protected terms with placeholders, so that no stage alters them, e.g. GitHub => protectedterma
+pub fn mask_protected_terms(text: &str, output: &mut String) {
+    let mut last = 0;
+    for (index, m) in find_protected_terms(text).iter().enumerate() {
+        output.push_str(&text[last..m.start()]);
+        output.push_str(&get_protected_term_placeholder(index));
+        last = m.end();
+    }
+    output.push_str(&text[last..]);
+}
+
+/// Replace placeholders with protected terms found by `extract_protected_terms`, e.g. protectedterma => GitHub
+pub fn restore_protected_terms(text: &str, terms: &str, output: &mut String) {
+    static PLACEHOLDER_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(&format!(r"\b{}([a-z]+)\b", PROTECTED_TERM_MARKER)).unwrap());
+    let terms = terms.split(' ').collect::<Vec<&str>>();
+    let result = PLACEHOLDER_REGEX.replace_all(text, |caps: &regex::Captures| {
+        let index = caps[1]
+            .bytes()
+            .fold(0, |index, letter| index * 26 + (letter - b'a') as usize);
+        terms.get(index).copied().unwrap_or(&caps[0]).to_owned()
+    });
+    write!(output, "{}", result).unwrap();
+}
+
+/// Get the reason why a token is protected from correction, None if it is not protected.
+fn get_protection_reason(word: &str) -> Option<&'static str> {
+    let protected_terms = &*PROTECTED_TERMS;
+    if let Some(reason) = protected_terms.exact.get(word) {
+        return Some(reason);
+    }
+    if let Some(reason) = protected_terms.exact_insensitive.get(&word.to_lowercase()) {
+        return Some(reason);
+    }
+    protected_terms
+        .regexes
+        .iter()
+        .find(|(regex, _)| regex.is_match(word))
+        .map(|(_, reason)| reason.as_str())
+}
+
+/// Check if a word is known by the dictionary or the frequency dictionary.
+pub fn is_known_word(word: &str) -> bool {
+    is_in_corpora(word) || ENGLISH_UNIGRAMS.0.contains_key(word)
+}
+
 /// Check if a word is a number.
 fn is_a_number(word: &str) -> bool {
-    match word.parse::<f64>() {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    word.parse::<f64>().is_ok()
 }
 
 /// Check if a word is something like a math equation (combination of numbers, symbols and punctuations).
@@ -188,8 +526,286 @@ fn is_punctuations_or_symbols(word: &str) -> bool {
         .all(|letter| ['P', 'S'].contains(&get_unicode_category(&letter)))
 }
 
-/// Algorithm to correct unknown word
-pub fn correct_unknown_word(word: &str) -> String {
+/// Correction of a token with its method, confidence is from 0 (guess) to 1 (certain).
+#[derive(Clone)]
+pub struct Correction {
+    pub original: String,
+    pub text: String,
+    pub method: &'static str,
+    pub confidence: f64,
+    // why the token was kept, e.g. reason of a protected term
+    pub reason: Option<&'static str>,
+    // corrected into a profanity lexicon term
+    pub profane: bool,
+}
+
+impl Correction {
+    fn new(original: &str, text: &str, method: &'static str, confidence: f64) -> Self {
+        Correction {
+            original: original.to_owned(),
+            text: text.to_owned(),
+            method,
+            confidence,
+            reason: None,
+            profane: false,
+        }
+    }
+
+    /// Token kept as is, e.g. known word, number, punctuation
+    fn unchanged(original: &str, text: &str) -> Self {
+        Correction::new(original, text, "none", 1.0)
+    }
+
+    /// Protected token, kept exactly as is (not lowercased)
+    fn protected(original: &str, reason: &'static str) -> Self {
+        let mut correction = Correction::new(original, original, "protected", 1.0);
+        correction.reason = Some(reason);
+        correction
+    }
+}
+
+/// Get the best suggestion (closest, then most frequent) with its confidence:
+/// similarity of edit distance times frequency ratio among the closest suggestions.
+fn get_best_suggestion(word: &str, suggestions: &[Suggestion]) -> Option<(String, f64)> {
+    let best = suggestions
+        .iter()
+        .min_by_key(|suggestion| (suggestion.distance, -suggestion.count))?;
+    let closest_count: i64 = suggestions
+        .iter()
+        .filter(|suggestion| suggestion.distance == best.distance)
+        .map(|suggestion| suggestion.count)
+        .sum();
+    let similarity = 1.0 - best.distance as f64 / word.chars().count().max(1) as f64;
+    let frequency_ratio = best.count as f64 / closest_count.max(1) as f64;
+    Some((best.term.to_owned(), similarity * frequency_ratio))
+}
+
+/// Get confidence of a segmentation: similarity of edit distance times average word probability.
+fn get_segmentation_confidence(
+    word: &str,
+    segmented: &str,
+    distance_sum: i64,
+    prob_log_sum: f64,
+) -> f64 {
+    let word_count = segmented.split_whitespace().count().max(1);
+    // spaces inserted between words are not edits, e.g. youarestupid => you are stupid
+    let edit_distance = (distance_sum - (word_count as i64 - 1)).max(0);
+    let similarity = 1.0 - edit_distance as f64 / word.chars().count().max(1) as f64;
+    let log_probability = prob_log_sum / word_count as f64;
+    let probability = (log_probability - SEGMENTATION_MIN_LOG_PROBABILITY)
+        / (SEGMENTATION_MAX_LOG_PROBABILITY - SEGMENTATION_MIN_LOG_PROBABILITY);
+    similarity.max(0.0) * probability.clamp(0.0, 1.0)
+}
+
+/// Get log10-probability of words by unigram frequency, e.g. you are => -4.9
+fn get_unigram_log_probability(words: &str) -> f64 {
+    let (unigrams, total) = &*ENGLISH_UNIGRAMS;
+    words
+        .split_whitespace()
+        .map(|word| {
+            let count = unigrams.get(word).copied().unwrap_or(0).max(1);
+            (count as f64 / *total as f64).log10()
+        })
+        .sum()
+}
+
+/// Correct a word toward its closest word if it is more likely (minus edit distance penalty)
+/// than the segmentation of the word, e.g. recieve => receive, but iamhere => i am here
+fn correct_closest_word(word: &str, segmented: &str) -> Option<Correction> {
+    if !word.chars().all(|letter| letter.is_alphabetic()) {
+        return None;
+    }
+    let max_edit_distance = CORRECTION_POLICY
+        .max_edit_distance(word)
+        .min(CLOSEST_WORD_MAX_EDIT_DISTANCE);
+    let suggestions = SYMSPELL.lookup(word, Verbosity::Closest, max_edit_distance);
+    let (term, confidence) = get_best_suggestion(word, &suggestions)?;
+    let distance = suggestions.first()?.distance;
+    let log_probability =
+        get_unigram_log_probability(&term) - distance as f64 * CLOSEST_WORD_EDIT_DISTANCE_PENALTY;
+    if log_probability < get_unigram_log_probability(segmented) {
+        return None;
+    }
+    Some(Correction::new(word, &term, "lookup", confidence))
+}
+
+/// Check if a word is two frequent words joined, e.g. sockpuppet => sock puppet
+pub fn is_known_compound(word: &str) -> bool {
+    let unigrams = &ENGLISH_UNIGRAMS.0;
+    let length = word.chars().count();
+    word.char_indices()
+        .enumerate()
+        .filter(|(index, _)| {
+            *index >= COMPOUND_MIN_PART_LENGTH && length - index >= COMPOUND_MIN_PART_LENGTH
+        })
+        .any(|(_, (split, _))| {
+            unigrams.contains_key(&word[..split]) && unigrams.contains_key(&word[split..])
+        })
+}
+
+/// Correct a word toward the profanity lexicon if a profane term is as close as the closest
+/// general word, unless it is a more frequent common word, e.g. biatch => bitch, fukin => fuckin,
+/// but borwn => brown (not porn), fak => far (not fat)
+fn correct_profane_word(word: &str) -> Option<Correction> {
+    let max_edit_distance = CORRECTION_POLICY.max_edit_distance(word);
+    let profane_suggestions =
+        PROFANITY_SYMSPELL.lookup(word, Verbosity::Closest, max_edit_distance);
+    let profane = profane_suggestions
+        .iter()
+        .max_by_key(|suggestion| suggestion.count)?;
+    // a compound of common words is not a misspelling, e.g. sockpuppet, not fuckpuppet
+    if profane.distance > 0 && is_known_compound(word) {
+        return None;
+    }
+    let general_suggestions = SYMSPELL.lookup(word, Verbosity::Closest, max_edit_distance);
+    if let Some(general) = general_suggestions
+        .iter()
+        .max_by_key(|suggestion| suggestion.count)
+    {
+        let is_common_word =
+            general.count >= PROFANITY_COMMON_WORD_MIN_COUNT && general.count > profane.count;
+        if general.distance < profane.distance
+            || (general.distance == profane.distance && is_common_word)
+        {
+            return None;
+        }
+    }
+    let similarity = 1.0 - profane.distance as f64 / word.chars().count() as f64;
+    let mut correction = Correction::new(word, &profane.term, "profanity", similarity);
+    correction.profane = true;
+    Some(correction)
+}
+
+/// Get phonetic key of a word (simplified Metaphone), e.g. phuck, fuhk => FK, shiit, sheeit, sheet => XT
+fn get_phonetic_key(word: &str) -> String {
+    let mut letters = word
+        .to_lowercase()
+        .chars()
+        .filter(|letter| letter.is_ascii_alphabetic())
+        .collect::<Vec<char>>();
+    // duplicate letters sound once, except c (e.g. accident)
+    letters.dedup_by(|letter, previous| letter == previous && *letter != 'c');
+    // silent or special first letters, e.g. knight, wrong, xylophone
+    match letters.as_slice() {
+        ['a', 'e', ..] | ['g', 'n', ..] | ['k', 'n', ..] | ['p', 'n', ..] | ['w', 'r', ..] => {
+            letters.remove(0);
+        }
+        ['x', ..] => letters[0] = 's',
+        ['w', 'h', ..] => {
+            letters.remove(1);
+        }
+        _ => {}
+    }
+    let is_vowel = |letter: Option<&char>| matches!(letter, Some('a' | 'e' | 'i' | 'o' | 'u'));
+    let mut key = String::new();
+    for (index, letter) in letters.iter().enumerate() {
+        let previous = index.checked_sub(1).and_then(|index| letters.get(index));
+        let next = letters.get(index + 1);
+        let after_next = letters.get(index + 2);
+        let plain = letter.to_ascii_uppercase().to_string();
+        let code = match letter {
+            'a' | 'e' | 'i' | 'o' | 'u' if index == 0 => "A",
+            'a' | 'e' | 'i' | 'o' | 'u' => "",
+            'b' if previous == Some(&'m') && next.is_none() => "",
+            'c' if next == Some(&'i') && after_next == Some(&'a') => "X",
+            'c' if next == Some(&'h') && previous == Some(&'s') => "K",
+            'c' if next == Some(&'h') => "X",
+            'c' if matches!(next, Some('i' | 'e' | 'y')) => "S",
+            'd' if next == Some(&'g') && matches!(after_next, Some('e' | 'i' | 'y')) => "J",
+            'd' => "T",
+            'g' if next == Some(&'h') && !is_vowel(after_next) => "",
+            'g' if next == Some(&'n') => "",
+            'g' if matches!(next, Some('i' | 'e' | 'y')) => "J",
+            'h' if matches!(previous, Some('c' | 's' | 'p' | 't' | 'g')) => "",
+            'h' if !is_vowel(next) => "",
+            'k' if previous == Some(&'c') => "",
+            'p' if next == Some(&'h') => "F",
+            's' if next == Some(&'h') => "X",
+            's' if next == Some(&'i') && matches!(after_next, Some('o' | 'a')) => "X",
+            't' if next == Some(&'i') && matches!(after_next, Some('o' | 'a')) => "X",
+            't' if next == Some(&'h') => "0",
+            't' if next == Some(&'c') && after_next == Some(&'h') => "",
+            'v' => "F",
+            'w' | 'y' if !is_vowel(next) => "",
+            'x' => "KS",
+            'c' | 'g' | 'q' => "K",
+            'z' => "S",
+            _ => plain.as_str(),
+        };
+        // same sounds in a row sound once, e.g. ck => K
+        if !code.is_empty() && !key.ends_with(code) {
+            key.push_str(code);
+        }
+    }
+    key
+}
+
+/// Count groups of vowels (about syllables), e.g. sheeeit => 1, hello => 2
+fn count_vowel_groups(word: &str) -> usize {
+    split_runs(
+        &word
+            .chars()
+            .map(|letter| if "aeiou".contains(letter) { 'a' } else { 'b' })
+            .collect::<String>(),
+    )
+    .iter()
+    .filter(|(letter, _)| *letter == 'a')
+    .count()
+}
+
+/// Get last vowel of a word, which shapes its sound most, e.g. sheeit => i, sheet => e
+fn get_last_vowel(word: &str) -> Option<char> {
+    word.chars().rev().find(|letter| "aeiou".contains(*letter))
+}
+
+/// Correct a word toward a swear word which sounds alike, e.g. phuck => fuck, azz => ass, sheeit => shit
+fn correct_phonetic_word(word: &str) -> Option<Correction> {
+    let key = get_phonetic_key(word);
+    if key.len() < PHONETIC_MIN_KEY_LENGTH {
+        return None;
+    }
+    let swear_word = PROFANITY_PHONETIC_INDEX.get(&key)?;
+    if count_vowel_groups(word) != count_vowel_groups(swear_word) {
+        return None;
+    }
+    // ambiguous if a close and common general word sounds the same, e.g. helo => hello, not hell,
+    // unless only the swear word keeps the last vowel, e.g. sheeit => shit, not sheet
+    let swear_word_count = ENGLISH_UNIGRAMS.0.get(swear_word).copied().unwrap_or(1) as f64;
+    let last_vowel = get_last_vowel(word);
+    let is_swear_word_vowel = get_last_vowel(swear_word) == last_vowel;
+    let is_ambiguous = SYMSPELL
+        .lookup(word, Verbosity::All, 1)
+        .iter()
+        .any(|suggestion| {
+            suggestion.count as f64 * PROFANITY_FREQUENCY_WEIGHT >= swear_word_count
+                && !ENGLISH_SWEAR_WORDS.contains(&suggestion.term)
+                && get_phonetic_key(&suggestion.term) == key
+                && (!is_swear_word_vowel || get_last_vowel(&suggestion.term) == last_vowel)
+        });
+    if is_ambiguous {
+        return None;
+    }
+    let mut correction = Correction::new(word, swear_word, "phonetic", PHONETIC_MATCH_CONFIDENCE);
+    correction.profane = true;
+    Some(correction)
+}
+
+/// Algorithm to correct unknown word, swear words are only matched if `match_profanity` is set
+pub fn correct_unknown_word(word: &str, match_profanity: bool) -> Correction {
+    // elongated known word, e.g. sooooo => so, fuuuuck => fuck
+    let normalized = normalize_elongation(word);
+    if is_elongated(word) && is_known_word(&normalized) {
+        let mut correction = Correction::new(word, &normalized, "elongation", 1.0);
+        correction.profane = ENGLISH_SWEAR_WORDS.contains(&normalized);
+        return correction;
+    }
+    // near matches to profane terms first, before they get normalized into innocent words
+    if match_profanity && word.chars().all(|letter| letter.is_alphabetic()) {
+        if let Some(correction) = correct_profane_word(word).or_else(|| correct_phonetic_word(word))
+        {
+            return correction;
+        }
+    }
     // first: with simple unknown word, replace all punctuations/symbols with space, try to correct
     // ex: hello.how.are.you
     let mut new_word = word
@@ -205,21 +821,28 @@ pub fn correct_unknown_word(word: &str) -> String {
     if new_word != word {
         let mut result_words: Vec<String> = Vec::new();
         let mut can_correct_flag = true;
-        for _word in new_word.split_whitespace().into_iter() {
+        let mut confidence: f64 = 1.0;
+        for _word in new_word.split_whitespace() {
             if is_in_corpora(_word) {
                 result_words.push(_word.to_owned());
                 continue;
             }
-            let suggestion = SYMSPELL.lookup(_word, Verbosity::Top, 2);
-            if suggestion.len() == 0 {
-                can_correct_flag = false;
-                break;
+            let max_edit_distance = CORRECTION_POLICY.max_edit_distance(_word);
+            let suggestions = SYMSPELL.lookup(_word, Verbosity::Closest, max_edit_distance);
+            match get_best_suggestion(_word, &suggestions) {
+                Some((term, term_confidence)) => {
+                    result_words.push(term);
+                    confidence = confidence.min(term_confidence);
+                }
+                None => {
+                    can_correct_flag = false;
+                    break;
+                }
             }
-            result_words.push(suggestion[0].term.to_owned());
         }
         if can_correct_flag {
             // print!("1>");
-            return result_words.join(" ");
+            return Correction::new(word, &result_words.join(" "), "lookup", confidence);
         }
     }
     // second: this word may be complex (multiple words and wrong spell), split and try to correct
@@ -227,55 +850,387 @@ pub fn correct_unknown_word(word: &str) -> String {
     // ex: he.l.loh.o.w.ar.ey.ou
     // print!("3>");
     new_word.retain(|letter| !letter.is_whitespace());
-    new_word = reduce_bigram(&new_word);
+    new_word = normalize_elongation(&new_word);
     // replace swear words
     //todo: whitelist?
-    let ac = AhoCorasick::builder()
-        .ascii_case_insensitive(true)
-        .match_kind(MatchKind::LeftmostLongest)
-        .build(&ENGLISH_SWEAR_WORDS.to_owned())
-        .unwrap();
-    new_word = ac.replace_all(&new_word, &ENGLISH_SWEAR_WORDS_REPLACEMENT);
+    if match_profanity {
+        let ac = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(ENGLISH_SWEAR_WORDS.to_owned())
+            .unwrap();
+        new_word = ac.replace_all(&new_word, &ENGLISH_SWEAR_WORDS_REPLACEMENT);
+    }
     // replace firstnames
     let ac = AhoCorasick::builder()
         .ascii_case_insensitive(true)
         .match_kind(MatchKind::LeftmostLongest)
-        .build(&ENGLISH_FIRSTNAMES.to_owned())
+        .build(ENGLISH_FIRSTNAMES.to_owned())
         .unwrap();
     new_word = ac.replace_all(&new_word, &ENGLISH_FIRSTNAMES_REPLACEMENT);
+    // spaces around replaced words would be segmented as empty unknown words
+    new_word = new_word.split_whitespace().collect::<Vec<&str>>().join(" ");
     // split text
-    let segmented_string = SYMSPELL
-        .word_segmentation(&new_word, ENGLISH_SEGMENTATION_MAX_EDIT_DISTANCE)
-        .segmented_string;
-    segmented_string
+    if new_word.chars().count() > CORRECTION_POLICY.max_segmentation_length {
+        return Correction::unchanged(word, word);
+    }
+    let composition =
+        SYMSPELL.word_segmentation(&new_word, CORRECTION_POLICY.segmentation_max_edit_distance);
+    // misspelled word rather than several rare words, e.g. recieve => receive, not rec i eve
+    if let Some(correction) = correct_closest_word(word, &composition.segmented_string) {
+        return correction;
+    }
+    if composition.segmented_string == word {
+        return Correction::unchanged(word, word);
+    }
+    let confidence = get_segmentation_confidence(
+        word,
+        &composition.segmented_string,
+        composition.distance_sum,
+        composition.prob_log_sum,
+    );
+    Correction::new(
+        word,
+        &composition.segmented_string,
+        "segmentation",
+        confidence,
+    )
 }
 
-pub fn process_text(text: &str, output: &mut String) {
-    let mut result_words: Vec<String> = Vec::new();
-    for word in text.split_whitespace().into_iter() {
-        let word = word.to_lowercase();
-        if is_a_number(&word) {
-            result_words.push(word);
+/// Correction candidate of a token, penalty is negative log-probability of choosing it.
+struct Candidate {
+    correction: Correction,
+    penalty: f64,
+}
+
+impl Candidate {
+    fn new(correction: Correction, penalty: f64) -> Self {
+        Candidate {
+            correction,
+            penalty,
+        }
+    }
+}
+
+/// Check if context correction can be used (bigram dictionary is loaded).
+fn is_context_correction_enabled() -> bool {
+    CORRECTION_MODE == CorrectionMode::Context && !ENGLISH_BIGRAMS.is_empty()
+}
+
+/// Get candidates of a known word: itself and its confused words, e.g. your => your, you are
+fn get_known_word_candidates(original: &str, word: &str) -> Vec<Candidate> {
+    let mut candidates = vec![Candidate::new(Correction::unchanged(original, word), 0.0)];
+    if !is_context_correction_enabled() {
+        return candidates;
+    }
+    if let Some(confusion_set) = ENGLISH_CONFUSION_SETS
+        .iter()
+        .find(|confusion_set| confusion_set.contains(&word))
+    {
+        candidates.extend(
+            confusion_set
+                .iter()
+                .filter(|confused_word| **confused_word != word)
+                .map(|confused_word| {
+                    let correction = Correction::new(original, confused_word, "confusion", 1.0);
+                    Candidate::new(correction, CONTEXT_CONFUSION_PENALTY)
+                }),
+        );
+    }
+    candidates
+}
+
+/// Get candidates of an unknown word: the corrected word, and all close words if using context.
+/// Corrections below the confidence threshold are dropped, the word is kept if none is left.
+fn get_unknown_word_candidates(
+    original: &str,
+    word: &str,
+    match_profanity: bool,
+) -> Vec<Candidate> {
+    let mut correction = correct_unknown_word(word, match_profanity);
+    correction.original = original.to_owned();
+    if correction.confidence < CORRECTION_CONFIDENCE_THRESHOLD {
+        let confidence = correction.confidence;
+        return vec![Candidate::new(
+            Correction::new(original, word, "uncertain", confidence),
+            0.0,
+        )];
+    }
+    let corrected = correction.text.clone();
+    let mut candidates = vec![Candidate::new(correction, 0.0)];
+    // a word kept as is (not correctable) has no alternatives
+    if !is_context_correction_enabled()
+        || corrected == word
+        || !word.chars().all(|letter| letter.is_alphabetic())
+    {
+        return candidates;
+    }
+    let max_edit_distance = CORRECTION_POLICY.max_edit_distance(word);
+    let suggestions = SYMSPELL.lookup(word, Verbosity::All, max_edit_distance);
+    let total_count: i64 = suggestions.iter().map(|suggestion| suggestion.count).sum();
+    for suggestion in suggestions.iter().take(CONTEXT_MAX_CANDIDATES) {
+        if candidates
+            .iter()
+            .any(|candidate| candidate.correction.text == suggestion.term)
+        {
+            continue;
+        }
+        let similarity = 1.0 - suggestion.distance as f64 / word.chars().count() as f64;
+        let confidence = similarity * suggestion.count as f64 / total_count.max(1) as f64;
+        let correction = Correction::new(original, &suggestion.term, "context", confidence);
+        let penalty = suggestion.distance as f64 * CONTEXT_EDIT_DISTANCE_PENALTY;
+        candidates.push(Candidate::new(correction, penalty));
+    }
+    candidates
+}
+
+/// Get log-probability of word after previous word, using stupid backoff.
+fn get_log_probability(previous_word: Option<&str>, word: &str) -> f64 {
+    let (unigrams, total) = &*ENGLISH_UNIGRAMS;
+    if let Some(previous_word) = previous_word {
+        let bigram_count = ENGLISH_BIGRAMS
+            .get(&format!("{} {}", previous_word, word))
+            .copied()
+            .unwrap_or(0);
+        let previous_count = unigrams.get(previous_word).copied().unwrap_or(0);
+        if bigram_count > 0 && previous_count > 0 {
+            return (bigram_count as f64 / previous_count as f64).ln();
+        }
+    }
+    let count = unigrams.get(word).copied().unwrap_or(0).max(1);
+    (CONTEXT_BACKOFF * count as f64 / *total as f64).ln()
+}
+
+/// Get score of candidate after previous candidate (also its inner words, e.g. you are).
+fn get_candidate_score(previous: Option<&Candidate>, candidate: &Candidate) -> f64 {
+    let mut previous_word =
+        previous.and_then(|previous| previous.correction.text.rsplit(' ').next());
+    let mut score = -candidate.penalty;
+    for word in candidate.correction.text.split(' ') {
+        score += get_log_probability(previous_word, word);
+        previous_word = Some(word);
+    }
+    score
+}
+
+/// Choose candidates of a sentence with the highest probability (Viterbi).
+fn choose_candidates_in_context(sentence: &[Vec<Candidate>]) -> Vec<Correction> {
+    // best score and previous candidate index of each candidate
+    let mut lattice: Vec<Vec<(f64, usize)>> = Vec::new();
+    for (index, candidates) in sentence.iter().enumerate() {
+        let scores = candidates
+            .iter()
+            .map(|candidate| {
+                if index == 0 {
+                    return (get_candidate_score(None, candidate), 0);
+                }
+                sentence[index - 1]
+                    .iter()
+                    .enumerate()
+                    .map(|(previous_index, previous)| {
+                        let score = lattice[index - 1][previous_index].0
+                            + get_candidate_score(Some(previous), candidate);
+                        (score, previous_index)
+                    })
+                    .fold((f64::NEG_INFINITY, 0), |best, current| {
+                        if current.0 > best.0 {
+                            current
+                        } else {
+                            best
+                        }
+                    })
+            })
+            .collect();
+        lattice.push(scores);
+    }
+    // backtrack from the best last candidate
+    let mut chosen = Vec::new();
+    let mut candidate_index = match lattice.last() {
+        Some(scores) => (0..scores.len())
+            .max_by(|a, b| scores[*a].0.total_cmp(&scores[*b].0))
+            .unwrap(),
+        None => return chosen,
+    };
+    for index in (0..sentence.len()).rev() {
+        chosen.push(sentence[index][candidate_index].correction.clone());
+        candidate_index = lattice[index][candidate_index].1;
+    }
+    chosen.reverse();
+    chosen
+}
+
+/// Choose candidates of all tokens, sentence by sentence.
+fn choose_candidates(tokens: Vec<Vec<Candidate>>) -> Vec<Correction> {
+    if !is_context_correction_enabled() {
+        return tokens
+            .into_iter()
+            .map(|mut candidates| candidates.swap_remove(0).correction)
+            .collect();
+    }
+    let mut result = Vec::new();
+    let mut sentence = Vec::new();
+    for candidates in tokens {
+        let is_sentence_end = candidates.len() == 1
+            && candidates[0]
+                .correction
+                .text
+                .chars()
+                .all(|letter| ['.', '!', '?'].contains(&letter));
+        sentence.push(candidates);
+        if is_sentence_end {
+            result.extend(choose_candidates_in_context(&sentence));
+            sentence.clear();
+        }
+    }
+    result.extend(choose_candidates_in_context(&sentence));
+    result
+}
+
+/// Correct all tokens of a text, once per row: the corrected text and correction columns are read from it.
+pub fn correct_text(text: &str) -> Vec<Correction> {
+    let mut tokens: Vec<Vec<Candidate>> = Vec::new();
+    let mut in_quote = false;
+    // dictionaries are loaded (on the first row) before the time budget starts
+    Lazy::force(&SYMSPELL);
+    Lazy::force(&ENGLISH_BIGRAMS);
+    Lazy::force(&ENGLISH_DICTIONARY);
+    Lazy::force(&PROFANITY_SYMSPELL);
+    Lazy::force(&PROFANITY_PHONETIC_INDEX);
+    let start = Instant::now();
+    for word in text.split_whitespace() {
+        // quote markers: keep if tagging, drop if excluding
+        if word == QUOTE_START_MARKER || word == QUOTE_END_MARKER {
+            in_quote = word == QUOTE_START_MARKER;
+            if QUOTE_MODE == QuoteMode::Tag {
+                tokens.push(vec![Candidate::new(Correction::unchanged(word, word), 0.0)]);
+            }
             continue;
         }
-        if is_punctuations_or_symbols(&word) {
-            result_words.push(word);
+        if is_pseudonym(word) {
+            tokens.push(vec![Candidate::new(Correction::unchanged(word, word), 0.0)]);
             continue;
         }
-        if is_math_equation(&word) {
-            result_words.push(word);
+        if let Some(reason) = get_protection_reason(word) {
+            tokens.push(vec![Candidate::new(
+                Correction::protected(word, reason),
+                0.0,
+            )]);
+            continue;
+        }
+        let original = word;
+        let word = word.to_lowercase();
+        if is_a_number(&word) || is_punctuations_or_symbols(&word) || is_math_equation(&word) {
+            tokens.push(vec![Candidate::new(
+                Correction::unchanged(original, &word),
+                0.0,
+            )]);
             continue;
         }
         if is_in_corpora(&word) {
-            result_words.push(word);
+            tokens.push(get_known_word_candidates(original, &word));
             continue;
         }
         if is_in_corpora(ENGLISH_STEMMER.stem(&word).borrow()) {
-            result_words.push(word);
+            tokens.push(vec![Candidate::new(
+                Correction::unchanged(original, &word),
+                0.0,
+            )]);
+            continue;
+        }
+        if word.chars().count() < CORRECTION_POLICY.min_correction_length {
+            tokens.push(vec![Candidate::new(
+                Correction::unchanged(original, &word),
+                0.0,
+            )]);
             continue;
         }
-        result_words.push(correct_unknown_word(&word));
+        if start.elapsed() > PROCESS_TEXT_TIME_BUDGET {
+            let correction = Correction::new(original, &word, "timeout", 0.0);
+            tokens.push(vec![Candidate::new(correction, 0.0)]);
+            continue;
+        }
+        let match_profanity = !(in_quote && QUOTE_MODE == QuoteMode::Exclude);
+        tokens.push(get_unknown_word_candidates(
+            original,
+            &word,
+            match_profanity,
+        ));
+    }
+    // close a quote left open by a previous stage, e.g. quoteend dropped with an html tag
+    if in_quote && QUOTE_MODE == QuoteMode::Tag {
+        let correction = Correction::unchanged(QUOTE_END_MARKER, QUOTE_END_MARKER);
+        tokens.push(vec![Candidate::new(correction, 0.0)]);
     }
-    let result = result_words.join(" ");
+    choose_candidates(tokens)
+}
+
+/// Get corrected text from corrections (see `correct_text`).
+pub fn apply_corrections(corrections: &[Correction]) -> String {
+    corrections
+        .iter()
+        .map(|correction| correction.text.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Find elongated words (emphasis), e.g. sooo,fuuuck
+pub fn extract_elongated_words(text: &str, output: &mut String) {
+    let result = text
+        .split_whitespace()
+        .filter(|word| is_elongated(word))
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(",");
     write!(output, "{}", result).unwrap();
 }
+
+/// Get original tokens of corrections, tab-separated.
+pub fn extract_original_tokens(corrections: &[Correction]) -> String {
+    corrections
+        .iter()
+        .map(|correction| correction.original.as_str())
+        .collect::<Vec<&str>>()
+        .join("\t")
+}
+
+/// Get corrected tokens (parallel to original tokens), tab-separated.
+pub fn extract_corrected_tokens(corrections: &[Correction]) -> String {
+    corrections
+        .iter()
+        .map(|correction| correction.text.as_str())
+        .collect::<Vec<&str>>()
+        .join("\t")
+}
+
+/// Find tokens corrected into profanity, separated by comma, e.g. phuck,biatch
+pub fn extract_profane_corrections(corrections: &[Correction]) -> String {
+    corrections
+        .iter()
+        .filter(|correction| correction.profane)
+        .map(|correction| correction.original.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Get changed, uncertain or protected tokens with method and confidence, e.g. teh => the (lookup 0.93)
+pub fn extract_correction_trace(corrections: &[Correction]) -> String {
+    corrections
+        .iter()
+        .filter(|correction| correction.method != "none")
+        .map(|correction| {
+            let mut trace = format!(
+                "{} => {} ({} {:.2}",
+                correction.original, correction.text, correction.method, correction.confidence
+            );
+            if let Some(reason) = correction.reason {
+                write!(trace, ": {}", reason).unwrap();
+            }
+            if correction.profane {
+                trace.push_str(", profane");
+            }
+            trace.push(')');
+            trace
+        })
+        .collect::<Vec<String>>()
+        .join("; ")
+}
@@ -34,6 +34,7 @@ pub fn get_unicode_block(letter: &char) -> &str {
 }
 
 /// Normalize string.
+#[allow(dead_code)]
 pub fn unicode_normalize(text: &str, output: &mut String, normalization_form: Option<&str>) {
     let nf = normalization_form.unwrap_or("nfkd");
     let result = match nf {
@@ -1,46 +1,283 @@
 use aho_corasick::{AhoCorasick, MatchKind};
 use linkify::{LinkFinder, LinkKind};
-use std::fmt::Write;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs::File,
+    io::{BufRead, BufReader},
+};
 
-/// Filter out all emails from text, using Aho-Corasick algorithm.
+use crate::pseudonymize;
+
+// top-level domains accepted in obfuscated links, e.g. example[dot]com
+const OBFUSCATED_LINK_TLDS: &str = "com|net|org|edu|gov|mil|int|info|biz|io|co|me|tv|ly|xyz|app|dev|online|site|uk|us|ca|au|de|fr|es|it|nl|ru|cn|jp|in|br|vn";
+// providers accepted in obfuscated emails with ambiguous plain " at ", e.g. john at gmail dot com
+const OBFUSCATED_EMAIL_PROVIDERS: [&str; 12] = [
+    "gmail",
+    "googlemail",
+    "yahoo",
+    "hotmail",
+    "outlook",
+    "live",
+    "msn",
+    "aol",
+    "icloud",
+    "protonmail",
+    "proton",
+    "gmx",
+];
+const OBFUSCATED_AT: &str =
+    r"\s*(?:\[\s*at\s*\]|\(\s*at\s*\)|\{\s*at\s*\}|<\s*at\s*>|\s+at\s+|@)\s*";
+const OBFUSCATED_DOT: &str = r"\s*(?:\[\s*(?:dot|\.)\s*\]|\(\s*(?:dot|\.)\s*\)|\{\s*(?:dot|\.)\s*\}|<\s*dot\s*>|\s+dot\s+|\.)\s*";
+
+static OBFUSCATED_DOT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!("(?i){}", OBFUSCATED_DOT)).unwrap());
+static OBFUSCATED_EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)\b([a-z0-9][a-z0-9._%+-]*?)({at})([a-z0-9-]+(?:{dot}[a-z0-9-]+)*{dot}(?:{tlds}))\b",
+        at = OBFUSCATED_AT,
+        dot = OBFUSCATED_DOT,
+        tlds = OBFUSCATED_LINK_TLDS
+    ))
+    .unwrap()
+});
+static OBFUSCATED_URL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)(?:\b(h(?:tt|xx)ps?)(?:\[:\]|:)//)?\b([a-z0-9-]+(?:{dot}[a-z0-9-]+)*{dot}(?:{tlds}))\b(/[^\s]*)?",
+        dot = OBFUSCATED_DOT,
+        tlds = OBFUSCATED_LINK_TLDS
+    ))
+    .unwrap()
+});
+
+/// Domain rules: `example.com` (domain and subdomains) and `*.example.com` (subdomains only).
+struct DomainBlocklist {
+    domains: HashMap<String, String>,
+    wildcards: HashMap<String, String>,
+}
+
+static DOMAIN_BLOCKLIST: Lazy<DomainBlocklist> = Lazy::new(|| {
+    eprintln!("Urls: domain blocklist loading...");
+    let mut blocklist = DomainBlocklist {
+        domains: HashMap::new(),
+        wildcards: HashMap::new(),
+    };
+    let domain_blocklist_filepath = "data/others/domain_blocklist.txt";
+    let file = File::open(domain_blocklist_filepath).unwrap();
+    let reader = BufReader::new(file);
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (domain, category) = match line.split_once(char::is_whitespace) {
+            Some((domain, category)) => (domain, category.trim()),
+            None => (line, "blocked"),
+        };
+        match domain.strip_prefix("*.") {
+            Some(domain) => {
+                if let Some(domain) = normalize_domain(domain) {
+                    blocklist.wildcards.insert(domain, category.to_owned());
+                }
+            }
+            None => {
+                if let Some(domain) = normalize_domain(domain) {
+                    blocklist.domains.insert(domain, category.to_owned());
+                }
+            }
+        }
+    }
+    blocklist
+});
+
+/// Normalize domain to lowercase ASCII (punycode for IDN), e.g. Bücher.Example. => xn--bcher-kva.example
+fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_end_matches('.');
+    if domain.is_empty() {
+        return None;
+    }
+    idna::domain_to_ascii(domain).ok()
+}
+
+/// Get host of url, e.g. https://user@www.example.com:8080/path?q=1 => www.example.com
+fn get_url_host(url: &str) -> &str {
+    let url = match url.find("://") {
+        Some(index) => &url[index + 3..],
+        None => url,
+    };
+    let authority = url.split(['/', '?', '#']).next().unwrap_or(url);
+    let host = match authority.rfind('@') {
+        Some(index) => &authority[index + 1..],
+        None => authority,
+    };
+    match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    }
+}
+
+/// Find category of domain in blocklist, also check its parent domains.
+fn get_domain_category(domain: &str) -> Option<&'static str> {
+    let domain = normalize_domain(domain)?;
+    if let Some(category) = DOMAIN_BLOCKLIST.domains.get(&domain) {
+        return Some(category);
+    }
+    let mut parent = domain.as_str();
+    while let Some((_, suffix)) = parent.split_once('.') {
+        if let Some(category) = DOMAIN_BLOCKLIST
+            .domains
+            .get(suffix)
+            .or_else(|| DOMAIN_BLOCKLIST.wildcards.get(suffix))
+        {
+            return Some(category);
+        }
+        parent = suffix;
+    }
+    None
+}
+
+/// Find categories of all urls/emails domains in blocklist, separated by comma.
+pub fn extract_domain_categories(text: &str, output: &mut String) {
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Email, LinkKind::Url]);
+    finder.url_must_have_scheme(false);
+
+    let mut categories: Vec<&str> = Vec::new();
+    for link in finder.links(text) {
+        let domain = match link.kind() {
+            LinkKind::Email => link.as_str().rsplit('@').next().unwrap_or_default(),
+            _ => get_url_host(link.as_str()),
+        };
+        if let Some(category) = get_domain_category(domain) {
+            if !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+    }
+    write!(output, "{}", categories.join(",")).unwrap();
+}
+
+/// Filter out all emails from text (or replace with pseudonym tokens), using Aho-Corasick algorithm.
 pub fn replace_emails(text: &str, output: &mut String) {
     let mut finder = LinkFinder::new();
     finder.kinds(&[LinkKind::Email]);
 
     let emails = finder
-        .links(&text)
-        .into_iter()
+        .links(text)
         .map(|link| link.as_str())
         .collect::<Vec<&str>>();
-    let emails_replacement = vec![" (email) "; emails.len()];
+    let emails_replacement = emails
+        .iter()
+        .map(|email| match pseudonymize("EMAIL", email) {
+            Some(token) => format!(" {} ", token),
+            None => " (email) ".to_owned(),
+        })
+        .collect::<Vec<String>>();
 
     let ac = AhoCorasick::builder()
         .ascii_case_insensitive(true)
         .match_kind(MatchKind::LeftmostLongest)
         .build(&emails)
         .unwrap();
-    let result = ac.replace_all(&text, &emails_replacement);
+    let result = ac.replace_all(text, &emails_replacement);
     write!(output, "{}", result).unwrap();
 }
 
-/// Filter out all urls from text, using Aho-Corasick algorithm.
+/// Filter out all urls from text (or replace with pseudonym tokens), using Aho-Corasick algorithm.
 pub fn replace_urls(text: &str, output: &mut String) {
     let mut finder = LinkFinder::new();
     finder.kinds(&[LinkKind::Url]);
     finder.url_must_have_scheme(false);
 
     let links = finder
-        .links(&text)
-        .into_iter()
+        .links(text)
         .map(|link| link.as_str())
         .collect::<Vec<&str>>();
-    let links_replacement = vec![" (url) "; links.len()];
+    let links_replacement = links
+        .iter()
+        .map(|link| match pseudonymize("URL", link) {
+            Some(token) => format!(" {} ", token),
+            None => " (url) ".to_owned(),
+        })
+        .collect::<Vec<String>>();
 
     let ac = AhoCorasick::builder()
         .ascii_case_insensitive(true)
         .match_kind(MatchKind::LeftmostLongest)
         .build(&links)
         .unwrap();
-    let result = ac.replace_all(&text, &links_replacement);
+    let result = ac.replace_all(text, &links_replacement);
+    write!(output, "{}", result).unwrap();
+}
+
+/// Check if a domain is obfuscated (not only plain dots), e.g. example[.]com, example(dot)com,
+/// spelled or spaced plain dots only if anchored, e.g. www dot example dot com (not connect the dot in the middle)
+fn is_obfuscated_domain(domain: &str, is_anchored: bool) -> bool {
+    OBFUSCATED_DOT_REGEX.find_iter(domain).any(|separator| {
+        let separator = separator.as_str();
+        match separator.trim() {
+            // spaced plain dot, e.g. www . example . com
+            "." => {
+                is_anchored
+                    && separator.starts_with(char::is_whitespace)
+                    && separator.ends_with(char::is_whitespace)
+            }
+            trimmed if trimmed.eq_ignore_ascii_case("dot") => is_anchored,
+            _ => true,
+        }
+    })
+}
+
+/// Convert obfuscated emails/urls to plain ones, e.g. john [at] gmail [dot] com => john@gmail.com, www dot example dot com => www.example.com
+pub fn deobfuscate_links(text: &str, output: &mut String) {
+    let result = OBFUSCATED_EMAIL_REGEX.replace_all(text, |caps: &Captures| {
+        let at = caps[2].trim().to_lowercase();
+        let domain = OBFUSCATED_DOT_REGEX
+            .replace_all(&caps[3], ".")
+            .to_lowercase();
+        // plain " at " is ambiguous, e.g. look at example dot com, 2 at example.com
+        let is_ambiguous = at == "at"
+            && !OBFUSCATED_EMAIL_PROVIDERS
+                .iter()
+                .any(|provider| domain.starts_with(&format!("{}.", provider)));
+        // plain emails are left to replace_emails
+        if (at == "@"
+            && caps[0] == caps[0].replace(char::is_whitespace, "")
+            && !is_obfuscated_domain(&caps[3], false))
+            || is_ambiguous
+        {
+            return caps[0].to_owned();
+        }
+        format!("{}@{}", &caps[1], domain)
+    });
+    let result = OBFUSCATED_URL_REGEX.replace_all(&result, |caps: &Captures| {
+        let is_defanged_scheme = caps
+            .get(1)
+            .is_some_and(|scheme| scheme.as_str().to_lowercase().contains("xx"));
+        // spelled or spaced plain dots are common in prose, only links with scheme, www,
+        // a known provider or several labels, e.g. mail dot example dot org
+        let domain = caps[2].to_lowercase();
+        let is_anchored = caps.get(1).is_some()
+            || domain.starts_with("www")
+            || OBFUSCATED_EMAIL_PROVIDERS
+                .iter()
+                .any(|provider| domain.starts_with(provider))
+            || OBFUSCATED_DOT_REGEX.find_iter(&domain).count() >= 2;
+        if !is_defanged_scheme && !is_obfuscated_domain(&domain, is_anchored) {
+            return caps[0].to_owned();
+        }
+        let scheme = caps
+            .get(1)
+            .map(|scheme| format!("{}://", scheme.as_str().to_lowercase().replace("xx", "tt")))
+            .unwrap_or_default();
+        let domain = OBFUSCATED_DOT_REGEX
+            .replace_all(&caps[2], ".")
+            .to_lowercase();
+        let path = caps.get(3).map_or("", |path| path.as_str());
+        format!("{}{}{}", scheme, domain, path)
+    });
     write!(output, "{}", result).unwrap();
 }
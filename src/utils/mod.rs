@@ -18,5 +18,20 @@ pub fn aho_corasick_replace_all(
         .match_kind(MatchKind::LeftmostLongest)
         .build(patterns)
         .unwrap();
-    ac.replace_all(&text, &replace_with)
+    ac.replace_all(text, &replace_with)
+}
+
+/// Check if a match is not a part of a longer word/number/version string, e.g. 12:34 in v1.12:34
+pub fn is_standalone(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let mut after = text[end..].chars();
+    let after_first = after.next();
+    let after_second = after.next();
+    if before.is_some_and(|c| c.is_alphanumeric() || ['.', ':', '_'].contains(&c)) {
+        return false;
+    }
+    if after_first.is_some_and(|c| c.is_alphanumeric() || [':', '_'].contains(&c)) {
+        return false;
+    }
+    !(after_first == Some('.') && after_second.is_some_and(|c| c.is_alphanumeric()))
 }
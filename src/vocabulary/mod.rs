@@ -0,0 +1,130 @@
+use once_cell::sync::Lazy;
+use polars::prelude::*;
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use symspell::Verbosity;
+
+use crate::{is_known_word, SYMSPELL};
+
+// a learned word must appear in this many documents...
+const LEARN_VOCABULARY_MIN_DOCUMENTS: usize = 20;
+// ...and be spread across them (documents / occurrences), not repeated in a few comments
+const LEARN_VOCABULARY_MIN_DOCUMENT_RATIO: f64 = 0.5;
+const LEARN_VOCABULARY_MIN_LENGTH: usize = 3;
+// a word one edit away from a much more frequent word of the corpus is a misspelling, e.g. becuase (because),
+// dont (don, as in don't), shorter words are mostly acronyms with frequent neighbors, e.g. afd (and)
+const LEARN_VOCABULARY_MISSPELLING_MIN_LENGTH: usize = 4;
+const LEARN_VOCABULARY_MISSPELLING_MIN_RATIO: f64 = 10.0;
+
+static VOCABULARY_TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[a-z]+\b").unwrap());
+
+/// Count occurrences and documents of words, e.g. infobox => (120, 85)
+fn count_words<'a>(texts: impl Iterator<Item = &'a str>) -> HashMap<String, (usize, usize)> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for text in texts {
+        let text = text.to_lowercase();
+        let mut document_words = HashSet::new();
+        // skip links, e.g. http://en.wikipedia.org => http, wikipedia, org
+        let words = text
+            .split_whitespace()
+            .filter(|token| !token.contains("://") && !token.starts_with("www."))
+            .flat_map(|token| VOCABULARY_TOKEN_REGEX.find_iter(token));
+        for m in words {
+            let word = m.as_str();
+            if word.len() < LEARN_VOCABULARY_MIN_LENGTH {
+                continue;
+            }
+            let count = counts.entry(word.to_owned()).or_insert((0, 0));
+            count.0 += 1;
+            if document_words.insert(word) {
+                count.1 += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Check if a word is one edit away from a much more frequent word of the corpus, e.g. becuase => because
+fn is_likely_misspelling(
+    word: &str,
+    count: usize,
+    counts: &HashMap<String, (usize, usize)>,
+) -> bool {
+    word.len() >= LEARN_VOCABULARY_MISSPELLING_MIN_LENGTH
+        && SYMSPELL
+            .lookup(word, Verbosity::All, 1)
+            .iter()
+            .filter(|suggestion| suggestion.term != word)
+            .any(|suggestion| {
+                counts
+                    .get(&suggestion.term)
+                    .is_some_and(|(neighbor_count, _)| {
+                        *neighbor_count as f64
+                            >= count as f64 * LEARN_VOCABULARY_MISSPELLING_MIN_RATIO
+                    })
+            })
+}
+
+/// Read a learned vocabulary (word count), empty if it does not exist yet.
+fn read_vocabulary(vocabulary_filepath: &str) -> HashMap<String, usize> {
+    let mut vocabulary = HashMap::new();
+    let file = match File::open(vocabulary_filepath) {
+        Ok(file) => file,
+        Err(_) => return vocabulary,
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        if let Some((word, count)) = line.split_once(' ') {
+            vocabulary.insert(word.to_owned(), count.trim().parse().unwrap_or(0));
+        }
+    }
+    vocabulary
+}
+
+/// Learn frequent and stable unknown words of a corpus (csv with `comment_text` column),
+/// merge them into the vocabulary file, written as a frequency dictionary (word count), e.g. infobox 120
+/// (previously learned words are known, so they are kept with their counts)
+pub fn learn_vocabulary(corpus_filepath: &str, vocabulary_filepath: &str) {
+    eprintln!("Vocabulary: learning from {}...", corpus_filepath);
+    let df = LazyCsvReader::new(corpus_filepath)
+        .with_has_header(true)
+        .finish()
+        .unwrap()
+        .select([col("comment_text")])
+        .collect()
+        .unwrap();
+    let texts = df.column("comment_text").unwrap().str().unwrap();
+    let counts = count_words(texts.into_iter().flatten());
+    let mut vocabulary = read_vocabulary(vocabulary_filepath);
+    let learned_count = vocabulary.len();
+    vocabulary.extend(
+        counts
+            .iter()
+            .filter(|(word, (count, document_count))| {
+                *document_count >= LEARN_VOCABULARY_MIN_DOCUMENTS
+                    && *document_count as f64 / *count as f64 >= LEARN_VOCABULARY_MIN_DOCUMENT_RATIO
+                    && !is_known_word(word)
+                    && !is_likely_misspelling(word, *count, &counts)
+            })
+            .map(|(word, (count, _))| (word.to_owned(), *count)),
+    );
+    let new_count = vocabulary.len() - learned_count;
+    let mut vocabulary = vocabulary.into_iter().collect::<Vec<(String, usize)>>();
+    vocabulary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let file = File::create(vocabulary_filepath).unwrap();
+    let mut writer = BufWriter::new(file);
+    for (word, count) in vocabulary.iter() {
+        writeln!(writer, "{} {}", word, count).unwrap();
+    }
+    eprintln!(
+        "Vocabulary: {} words ({} new) written to {}",
+        vocabulary.len(),
+        new_count,
+        vocabulary_filepath
+    );
+}
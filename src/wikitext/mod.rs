@@ -0,0 +1,160 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::fmt::Write;
+
+// namespaces of links which are not a part of prose
+const DROPPED_LINK_NAMESPACES: [&str; 4] = ["file", "image", "category", "media"];
+// namespaces of links whose target is kept (e.g. for usernames pseudonymization)
+const KEPT_LINK_NAMESPACES: [&str; 2] = ["user", "user talk"];
+
+static NOWIKI_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<nowiki\s*>(.*?)</nowiki\s*>").unwrap());
+static COMMENT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?(-->|$)").unwrap());
+static REF_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<ref(\s[^>]*)?/>|<ref(\s[^>]*)?>.*?</ref\s*>|<references\s*/>").unwrap()
+});
+static TEMPLATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{[^{}]*\}\}").unwrap());
+static INTERNAL_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\[\]|]*)(?:\|([^\[\]]*))?\]\]").unwrap());
+static EXTERNAL_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[((?:https?:)?//[^\s\[\]]+)(?:\s+([^\[\]]*))?\]").unwrap());
+static BOLD_ITALIC_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"'{2,}").unwrap());
+static HEADING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(=+)\s*(.*?)\s*=+$").unwrap());
+static TABLE_CELL_ATTRIBUTES_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^\s*(?:[\w-]+\s*=\s*("[^"]*"|'[^']*'|[^\s|]+)\s*)+\|"#).unwrap());
+
+/// Replace internal link by its label, e.g. [[dog|puppy]] => puppy, [[File:a.jpg|thumb]] => ""
+fn replace_internal_link(caps: &Captures) -> String {
+    let target = caps[1].trim();
+    let namespace = target
+        .split_once(':')
+        .map(|(namespace, _)| namespace.trim().to_lowercase().replace('_', " "));
+    if let Some(namespace) = namespace {
+        if DROPPED_LINK_NAMESPACES.contains(&namespace.as_str()) {
+            return String::new();
+        }
+        if KEPT_LINK_NAMESPACES.contains(&namespace.as_str()) {
+            return target.to_owned();
+        }
+    }
+    match caps.get(2) {
+        Some(label) if !label.as_str().trim().is_empty() => label.as_str().trim().to_owned(),
+        _ => target.trim_start_matches(':').to_owned(),
+    }
+}
+
+/// Strip table markup from a line, return None if the line has no content, e.g. | a || b => a b
+fn strip_table_line(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("{|") || trimmed.starts_with("|}") || trimmed.starts_with("|-") {
+        return None;
+    }
+    let cells = if let Some(caption) = trimmed.strip_prefix("|+") {
+        vec![caption]
+    } else if let Some(cells) = trimmed.strip_prefix('|') {
+        cells.split("||").collect()
+    } else if let Some(cells) = trimmed.strip_prefix('!') {
+        cells
+            .split("!!")
+            .flat_map(|cell| cell.split("||"))
+            .collect()
+    } else {
+        return Some(line.to_owned());
+    };
+    let result = cells
+        .iter()
+        .map(|cell| {
+            TABLE_CELL_ATTRIBUTES_REGEX
+                .replace(cell, "")
+                .trim()
+                .to_owned()
+        })
+        .filter(|cell| !cell.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ");
+    Some(result)
+}
+
+/// Strip list/indentation markers at line start, e.g. "::* reply" => "reply"
+/// `#` is only a marker before whitespace or another marker, e.g. "#hashtag" is kept
+fn strip_indentation(line: &str) -> &str {
+    let mut rest = line;
+    loop {
+        let mut letters = rest.chars();
+        match (letters.next(), letters.next()) {
+            (Some(':' | '*' | ';'), _) => rest = &rest[1..],
+            (Some('#'), None) => rest = &rest[1..],
+            (Some('#'), Some(next)) if next.is_whitespace() || ":*#;".contains(next) => {
+                rest = &rest[1..]
+            }
+            _ => break,
+        }
+    }
+    match rest.len() == line.len() {
+        true => line,
+        false => rest.trim_start(),
+    }
+}
+
+/// Strip wikitext markup: links, templates, refs, bold/italic, headings, tables, indentations.
+pub fn strip_wikitext(text: &str, output: &mut String) {
+    // keep nowiki contents away from markup stripping
+    let mut nowikis: Vec<String> = Vec::new();
+    let mut result = NOWIKI_REGEX
+        .replace_all(text, |caps: &Captures| {
+            nowikis.push(caps[1].to_owned());
+            format!("\u{1}{}\u{1}", nowikis.len() - 1)
+        })
+        .into_owned();
+    result = COMMENT_REGEX.replace_all(&result, "").into_owned();
+    result = REF_REGEX.replace_all(&result, " ").into_owned();
+    // nested templates, from the innermost
+    while TEMPLATE_REGEX.is_match(&result) {
+        result = TEMPLATE_REGEX.replace_all(&result, " ").into_owned();
+    }
+    // nested links (e.g. in file captions), from the innermost
+    while INTERNAL_LINK_REGEX.is_match(&result) {
+        result = INTERNAL_LINK_REGEX
+            .replace_all(&result, replace_internal_link)
+            .into_owned();
+    }
+    result = EXTERNAL_LINK_REGEX
+        .replace_all(&result, |caps: &Captures| match caps.get(2) {
+            Some(label) => format!("{} {}", label.as_str(), &caps[1]),
+            None => caps[1].to_owned(),
+        })
+        .into_owned();
+    result = BOLD_ITALIC_REGEX.replace_all(&result, "").into_owned();
+    // line-based markups
+    let mut in_table = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in result.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("{|") {
+            in_table = true;
+        }
+        if in_table {
+            if trimmed.starts_with("|}") {
+                in_table = false;
+            }
+            if let Some(line) = strip_table_line(line) {
+                lines.push(line);
+            }
+            continue;
+        }
+        if trimmed.starts_with("----") {
+            continue;
+        }
+        if let Some(caps) = HEADING_REGEX.captures(trimmed) {
+            lines.push(caps[2].to_owned());
+            continue;
+        }
+        lines.push(strip_indentation(line).to_owned());
+    }
+    result = lines.join("\n");
+    // restore nowiki contents
+    for (index, nowiki) in nowikis.iter().enumerate() {
+        result = result.replace(&format!("\u{1}{}\u{1}", index), nowiki);
+    }
+    write!(output, "{}", result).unwrap();
+}